@@ -9,18 +9,22 @@ use crate::{
 };
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     rc::Rc,
     vec,
 };
 
 use super::opcodes::{Ins, Reg};
 
+/// Variables hidden by a loop scope, with the registers they had before it
+type Shadowed = Vec<(String, Option<Reg>)>;
+
 pub struct Compiler<'a> {
     env: &'a mut Env,
     curr_seg: usize,
-    loop_begins: Vec<usize>,
+    loop_begins: Vec<Option<usize>>,
     end_jumps: Vec<usize>,
+    continue_jumps: Vec<usize>,
 }
 
 impl<'a> Compiler<'a> {
@@ -30,6 +34,7 @@ impl<'a> Compiler<'a> {
             curr_seg: 0,
             loop_begins: Vec::new(),
             end_jumps: Vec::new(),
+            continue_jumps: Vec::new(),
         }
     }
 
@@ -42,7 +47,7 @@ impl<'a> Compiler<'a> {
     }
 
     fn global_seg(&self) -> &Segment {
-        &self.env.segments().iter().find(|s| s.is_global()).unwrap()
+        self.env.segments().iter().find(|s| s.is_global()).unwrap()
     }
 
     fn with(&mut self, ins: Ins) -> &mut Self {
@@ -78,47 +83,105 @@ impl<'a> Compiler<'a> {
         self.seg_mut().push_pos(n.pos());
         match n.ast() {
             Ast::If(e0, b0, b1) => self.compile_if(e0, b0, b1),
-            Ast::While(e0, b0) => self.compile_while(e0, b0),
+            Ast::While(e0, b0) => self.compile_while(e0, b0, n.pos()),
+            Ast::For(ids, e0, b0) => self.compile_for(ids, e0, b0),
             Ast::FuncDef(a, b, c) => self.compile_function(None, a, b, c, n.pos()),
             Ast::Let(id, e0) => self.compile_let(id, e0, n.pos()),
+            Ast::LetObject(ids, e0) => self.compile_let_object(ids, e0, n.pos()),
+            Ast::LetArray(ids, e0) => self.compile_let_array(ids, e0, n.pos()),
             Ast::Assign(op, reference, e0) => self.compile_assign(*op, reference, e0),
-            Ast::Call(f, args) => self.compile_call(self.seg().spare_reg(), f, args),
-            Ast::Return(e0) if self.seg().is_local() => self.compile_return(e0),
-            Ast::Return(_) => error::Error::invalid_return_position(n.pos()).err(),
+            Ast::Block(_) => self.compile_block(n),
+            Ast::Return(e0) => self.compile_return(e0),
             Ast::Break => {
                 self.end_jumps.push(self.seg().count());
                 Ok(self.with(Ins::Nop))
             }
             Ast::Continue => match self.loop_begins.last() {
-                Some(i) => Ok(self.with(Ins::Jump(*i))),
+                Some(Some(i)) => Ok(self.with(Ins::Jump(*i))),
+                Some(None) => {
+                    self.continue_jumps.push(self.seg().count());
+                    Ok(self.with(Ins::Nop))
+                }
                 None => error::Error::invalid_continue_pos(n.pos()).err(),
             },
-            _ => unreachable!(),
+            _ => self.compile_expr(self.seg().spare_reg(), n),
         }
     }
 
+    /// Compiles a script imported as a module into its own function segment,
+    /// returning the segment id. The module evaluates to an object of its top
+    /// level declarations unless it returns a value itself.
+    pub fn compile_module(&mut self, name: &str, tree: &AstNode) -> Result<usize, error::Error> {
+        let fid = self.env.new_seg(
+            Segment::new(
+                name.to_string(),
+                false,
+                0,
+                1,
+                vec![],
+                vec![],
+                HashMap::new(),
+                HashMap::new(),
+                None,
+                BTreeMap::new(),
+            )
+            .with_pos(tree.pos()),
+        );
+
+        let old_segment = self.curr_seg;
+        self.curr_seg = fid;
+
+        let exports = match tree.ast() {
+            Ast::Block(v) => v
+                .iter()
+                .flat_map(|n| match n.ast() {
+                    Ast::Let(id, _) | Ast::FuncDef(Some(id), _, _) => vec![id.clone()],
+                    Ast::LetObject(ids, _) | Ast::LetArray(ids, _) => ids.clone(),
+                    _ => vec![],
+                })
+                .map(|id| {
+                    let key = AstNode::new(Ast::String(id.clone()), tree.pos());
+                    (key, AstNode::new(Ast::Reference(id), tree.pos()))
+                })
+                .collect(),
+            _ => vec![],
+        };
+
+        let result = self.compile_block(tree).and_then(|s| {
+            let exports = AstNode::new(Ast::Object(exports), tree.pos());
+            s.compile_return(&Some(Box::new(exports))).map(drop)
+        });
+
+        self.curr_seg = old_segment;
+        result.map(|_| fid)
+    }
+
     fn compile_function(
         &mut self,
         r: Option<Reg>,
         name: &Option<String>,
-        args: &Vec<String>,
+        args: &[String],
         body: &AstNode,
         pos: io::Pos,
     ) -> Result<&mut Self, error::Error> {
-        let fid = self.env.new_seg(Segment::new(
-            name.clone().unwrap_or("<lambda>".to_string()),
-            false,
-            Reg::try_from(args.len()).unwrap() + 1,
-            vec![],
-            vec![],
-            args.iter()
-                .enumerate()
-                .map(|(i, v)| (v.to_string(), Reg::try_from(i).unwrap()))
-                .collect(),
-            HashMap::new(),
-            Some(self.curr_seg),
-            BTreeMap::new(),
-        ));
+        let fid = self.env.new_seg(
+            Segment::new(
+                name.clone().unwrap_or("<lambda>".to_string()),
+                false,
+                Reg::try_from(args.len()).unwrap(),
+                Reg::try_from(args.len()).unwrap() + 1,
+                vec![],
+                vec![],
+                args.iter()
+                    .enumerate()
+                    .map(|(i, v)| (v.to_string(), Reg::try_from(i).unwrap()))
+                    .collect(),
+                HashMap::new(),
+                Some(self.curr_seg),
+                BTreeMap::new(),
+            )
+            .with_pos(pos),
+        );
 
         let fr = match name {
             None => Ok(r.unwrap()),
@@ -131,22 +194,34 @@ impl<'a> Compiler<'a> {
         let old_segment = self.curr_seg;
         self.curr_seg = fid;
 
+        // local functions are not globally visible, so bind own name for recursion
+        if let Some(name) = name
+            .as_ref()
+            .filter(|_| self.env.get_segment(old_segment).is_local())
+        {
+            if let Some(r) = self.seg_mut().new_symbol(name.to_string()) {
+                self.with(Ins::LoadSelf(r));
+            }
+        }
+
         self.compile_block(body)?;
-        if !matches!(self.seg().ins().last(), Some(Ins::RetNone | Ins::Ret(_))) {
+        if !Self::always_returns(body) {
             self.with(Ins::RetNone);
         }
 
         self.curr_seg = old_segment;
 
-        if self.seg().is_global() {
-            self.with(Ins::LoadF(r.unwrap_or(0), fid));
+        // at global scope the function is built in a scratch register before `SetG`
+        let dst = if self.seg().is_global() {
+            r.unwrap_or(0)
         } else {
-            self.with(Ins::LoadF(fr, fid));
-        }
+            fr
+        };
+        self.with(Ins::LoadF(dst, fid));
 
         let r0 = r
             .map(|r| r + 1)
-            .unwrap_or(self.seg().is_global().then_some(1).unwrap_or(0) + self.seg().spare_reg());
+            .unwrap_or(if self.seg().is_global() { 1 } else { 0 } + self.seg().spare_reg());
 
         let func = self.env.get_segment_mut(fid);
         if let uc @ 1.. = func.up_values().len() {
@@ -155,18 +230,81 @@ impl<'a> Compiler<'a> {
                 .iter()
                 .try_for_each(|(v0, i)| self.compile_id(r0 + i, v0, pos).map(|_| ()))?;
 
-            self.with(Ins::Close(fr, r0, r0 + Reg::try_from(uc).unwrap()))
+            self.with(Ins::Close(dst, r0, r0 + Reg::try_from(uc).unwrap()))
                 .seg_mut()
                 .inc_slots(r0 + Reg::try_from(uc).unwrap())
         }
 
-        if self.seg().is_global() && matches!(name, Some(_)) {
-            self.with(Ins::SetG(fr, r.unwrap_or(0)));
+        if self.seg().is_global() && name.is_some() {
+            self.with(Ins::SetG(fr, dst));
         }
 
         Ok(self)
     }
 
+    /// Whether every control path through the statement ends in a `return`
+    fn always_returns(n: &AstNode) -> bool {
+        match n.ast() {
+            Ast::Return(_) => true,
+            Ast::Block(v) => v.last().is_some_and(Self::always_returns),
+            Ast::If(_, b0, Some(b1)) => Self::always_returns(b0) && Self::always_returns(b1),
+            _ => false,
+        }
+    }
+
+    /// Value of an integer literal, possibly negated
+    fn integer_literal(n: &AstNode) -> Option<i64> {
+        match n.ast() {
+            Ast::Int(i) => Some(*i),
+            Ast::UnaryExp(Op::Sub, e0) => Self::integer_literal(e0).map(i64::wrapping_neg),
+            _ => None,
+        }
+    }
+
+    /// Truthiness of an expression built only from literals, if it can be known
+    /// at compile time
+    fn constant_truthiness(n: &AstNode) -> Option<bool> {
+        match n.ast() {
+            Ast::Null => Some(false),
+            Ast::Bool(b) => Some(*b),
+            Ast::Int(i) => Some(*i != 0),
+            Ast::Float(f) => Some(*f != 0.0),
+            Ast::String(s) => Some(!s.is_empty()),
+            Ast::UnaryExp(Op::Not, e0) => Self::constant_truthiness(e0).map(|b| !b),
+            Ast::BinaryExp(Op::And, e0, e1) => {
+                match (Self::constant_truthiness(e0), Self::constant_truthiness(e1)) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            Ast::BinaryExp(Op::Or, e0, e1) => {
+                match (Self::constant_truthiness(e0), Self::constant_truthiness(e1)) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a loop body contains a statement that leaves the loop, breaks
+    /// only count when they belong to this loop rather than a nested one
+    fn exits_loop(n: &AstNode, outer: bool) -> bool {
+        match n.ast() {
+            Ast::Return(_) => true,
+            Ast::Break => outer,
+            Ast::Block(v) => v.iter().any(|n| Self::exits_loop(n, outer)),
+            Ast::If(_, b0, b1) => {
+                Self::exits_loop(b0, outer)
+                    || b1.as_ref().is_some_and(|b1| Self::exits_loop(b1, outer))
+            }
+            Ast::While(_, b0) | Ast::For(_, _, b0) => Self::exits_loop(b0, false),
+            _ => false,
+        }
+    }
+
     fn compile_let(
         &mut self,
         id: &String,
@@ -180,6 +318,73 @@ impl<'a> Compiler<'a> {
         }
     }
 
+    fn compile_let_object(
+        &mut self,
+        ids: &[String],
+        e0: &AstNode,
+        pos: io::Pos,
+    ) -> Result<&mut Self, error::Error> {
+        let mut symbols = vec![];
+        for id in ids.iter() {
+            match self.seg_mut().new_symbol(id.to_string()) {
+                Some(r) => symbols.push(r),
+                None => return error::Error::duplicate_var_name(id.to_string(), pos).err(),
+            }
+        }
+
+        let r = self.seg().spare_reg();
+        self.seg_mut().inc_slots(r + 2);
+        self.compile_expr(r, e0)?;
+
+        for (id, sym) in ids.iter().zip(symbols) {
+            let k = self
+                .seg_mut()
+                .storek(Value::String(Rc::new(id.to_string())));
+
+            self.with(Ins::LoadK(r + 1, k));
+            if self.seg().is_local() {
+                self.with(Ins::ObjGetStrict(sym, r, r + 1));
+            } else {
+                self.with(Ins::ObjGetStrict(r + 2, r, r + 1))
+                    .with(Ins::SetG(sym, r + 2));
+            }
+        }
+
+        Ok(self)
+    }
+
+    fn compile_let_array(
+        &mut self,
+        ids: &[String],
+        e0: &AstNode,
+        pos: io::Pos,
+    ) -> Result<&mut Self, error::Error> {
+        let mut symbols = vec![];
+        for id in ids.iter() {
+            match self.seg_mut().new_symbol(id.to_string()) {
+                Some(r) => symbols.push(r),
+                None => return error::Error::duplicate_var_name(id.to_string(), pos).err(),
+            }
+        }
+
+        let r = self.seg().spare_reg();
+        self.seg_mut().inc_slots(r + 2);
+        self.compile_expr(r, e0)?
+            .with(Ins::Unpack(r, symbols.len() as Reg));
+
+        for (i, sym) in symbols.into_iter().enumerate() {
+            self.load_int(r + 1, i as i64);
+            if self.seg().is_local() {
+                self.with(Ins::ObjGet(sym, r, r + 1));
+            } else {
+                self.with(Ins::ObjGet(r + 2, r, r + 1))
+                    .with(Ins::SetG(sym, r + 2));
+            }
+        }
+
+        Ok(self)
+    }
+
     fn compile_assign(
         &mut self,
         op: Op,
@@ -188,27 +393,24 @@ impl<'a> Compiler<'a> {
     ) -> Result<&mut Self, error::Error> {
         let r = self.seg().spare_reg();
 
+        let ins = |r0, r1, r2| {
+            op.to_ins(r0, r1, r2)
+                .ok_or_else(|| error::Error::non_assign_op(op, v.pos()))
+        };
+
         let id = match v.ast() {
             Ast::Reference(id) => Ok(id),
             Ast::Subscript(e1, e2) => {
-                self.seg_mut().inc_slots(r + 2);
-                return Ok(self
-                    .compile_expr(r, e1)?
-                    .compile_expr(r + 1, e2)?
-                    .compile_expr(r + 2, e0)?
-                    .with(Ins::ObjIns(r, r + 1, r + 2)));
+                self.compile_expr(r, e1)?.compile_expr(r + 1, e2)?;
+                return self.compile_member_assign(r, op, e0, ins);
             }
             Ast::Deref(e1, e2) => {
-                self.seg_mut().inc_slots(r + 2);
                 let k = self
                     .seg_mut()
                     .storek(Value::String(Rc::new(e2.to_string())));
 
-                return Ok(self
-                    .compile_expr(r, e1)?
-                    .with(Ins::LoadK(r + 1, k))
-                    .compile_expr(r + 2, e0)?
-                    .with(Ins::ObjIns(r, r + 1, r + 2)));
+                self.compile_expr(r, e1)?.with(Ins::LoadK(r + 1, k));
+                return self.compile_member_assign(r, op, e0, ins);
             }
             _ => error::Error::invalid_ast_node(v.pos()).err(),
         }?;
@@ -219,21 +421,40 @@ impl<'a> Compiler<'a> {
         let local_reg = self
             .seg()
             .is_local()
-            .then(|| ())
+            .then_some(())
             .and_then(|_| self.seg().locals().get(id));
 
         match (global_reg, local_reg) {
             (Some(&gr), None) if op == Op::Assign => Ok(self.with(Ins::SetG(gr, 0))),
             (_, Some(&lr)) if op == Op::Assign => Ok(self.with(Ins::Move(lr, r))),
-            (_, Some(&lr)) => Ok(self.with(op.to_ins(lr, lr, r))),
+            (_, Some(&lr)) => Ok(self.with(ins(lr, lr, r)?)),
             (Some(&gr), None) => Ok(self
                 .with(Ins::LoadG(r + 1, gr))
-                .with(op.to_ins(r, r + 1, r))
+                .with(ins(r, r + 1, r)?)
                 .with(Ins::SetG(gr, r))),
             (None, None) => error::Error::mutate_closure(id.to_string(), v.pos()).err(),
         }
     }
 
+    /// Assigns to the container in `r` at the key in `r + 1`
+    fn compile_member_assign(
+        &mut self,
+        r: Reg,
+        op: Op,
+        e0: &AstNode,
+        ins: impl Fn(Reg, Reg, Reg) -> Result<Ins, error::Error>,
+    ) -> Result<&mut Self, error::Error> {
+        self.seg_mut().inc_slots(r + 3);
+        self.compile_expr(r + 2, e0)?;
+
+        if op != Op::Assign {
+            self.with(Ins::ObjGet(r + 3, r, r + 1))
+                .with(ins(r + 2, r + 3, r + 2)?);
+        }
+
+        Ok(self.with(Ins::ObjIns(r, r + 1, r + 2)))
+    }
+
     fn compile_return(&mut self, e0: &Option<Box<AstNode>>) -> Result<&mut Self, error::Error> {
         match e0 {
             None => Ok(self.with(Ins::RetNone)),
@@ -244,17 +465,34 @@ impl<'a> Compiler<'a> {
         }
     }
 
-    fn compile_while(&mut self, e0: &AstNode, b0: &AstNode) -> Result<&mut Self, error::Error> {
-        let r = self.seg().spare_reg();
+    fn compile_while(
+        &mut self,
+        e0: &AstNode,
+        b0: &AstNode,
+        pos: io::Pos,
+    ) -> Result<&mut Self, error::Error> {
+        if Self::constant_truthiness(e0) == Some(true) && !Self::exits_loop(b0, true) {
+            return error::Error::infinite_loop(pos).err();
+        }
 
         let jmp0 = self.seg().count();
-        self.loop_begins.push(jmp0);
+        self.loop_begins.push(Some(jmp0));
+
+        let (r, shadowed) = match e0.ast() {
+            Ast::Let(id, e1) => self.compile_loop_binding(id, e1)?,
+            _ => {
+                let r = self.seg().spare_reg();
+                self.compile_expr(r, e0)?;
+                (r, vec![])
+            }
+        };
 
-        let jmp1 = self.compile_expr(r, e0)?.seg().count();
+        let jmp1 = self.seg().count();
         let breaks_start = self.end_jumps.len();
 
         let jmp2 = self.with(Ins::Nop).compile_block(b0)?.seg().count() + 1;
         self.loop_begins.pop();
+        self.end_scope(shadowed);
 
         for i in breaks_start..self.end_jumps.len() {
             self.set_ins(self.end_jumps[i], Ins::Jump(jmp2));
@@ -266,6 +504,252 @@ impl<'a> Compiler<'a> {
             .with(Ins::Jump(jmp0)))
     }
 
+    /// Iterates over an array or string, the iterable, index and current
+    /// element are kept in hidden symbols so the body is free to use any
+    /// spare registers
+    fn compile_for(
+        &mut self,
+        ids: &[String],
+        e0: &AstNode,
+        b0: &AstNode,
+    ) -> Result<&mut Self, error::Error> {
+        if let Ast::Range(e1, e2, e3) = e0.ast() {
+            let step = match e3 {
+                None => 1,
+                Some(e3) => Self::integer_literal(e3)
+                    .filter(|step| *step != 0)
+                    .ok_or_else(|| error::Error::invalid_step(e3.pos()))?,
+            };
+            return self.compile_for_range(ids, e1, e2, step, b0);
+        }
+
+        let label = self.seg().count();
+        let hidden: Vec<Reg> = (0..3)
+            .map(|i| {
+                self.seg_mut()
+                    .get_or_create_symbol(format!("<for {}:{}>", label, i))
+            })
+            .collect();
+
+        let local = self.seg().is_local();
+        let r = if local { hidden[0] } else { 0 };
+        self.seg_mut().inc_slots(r + 3);
+
+        self.compile_expr(r, e0)?.load_int(r + 1, 0);
+        if !local {
+            self.with(Ins::SetG(hidden[0], r))
+                .with(Ins::SetG(hidden[1], r + 1));
+        }
+
+        let jmp0 = self.seg().count();
+        self.loop_begins.push(Some(jmp0));
+
+        if !local {
+            self.with(Ins::LoadG(r, hidden[0]))
+                .with(Ins::LoadG(r + 1, hidden[1]));
+        }
+
+        let jmp1 = self.with(Ins::Nop).seg().count() - 1;
+        if !local {
+            self.with(Ins::SetG(hidden[1], r + 1));
+        }
+
+        let (symbols, shadowed) = self.begin_scope(ids);
+        self.compile_loop_targets(&symbols, r + 2, r + 3);
+
+        let breaks_start = self.end_jumps.len();
+        self.compile_block(b0)?.with(Ins::Jump(jmp0));
+        self.loop_begins.pop();
+        self.end_scope(shadowed);
+
+        let end = self.seg().count();
+        for i in breaks_start..self.end_jumps.len() {
+            self.set_ins(self.end_jumps[i], Ins::Jump(end));
+        }
+        self.end_jumps.truncate(breaks_start);
+
+        self.set_ins(jmp1, Ins::IterNext(r, end))
+            .with(Ins::LoadN(r));
+        if !local {
+            self.with(Ins::SetG(hidden[0], r));
+        }
+
+        Ok(self)
+    }
+
+    /// Declares loop variables afresh for the loop body, returning their
+    /// registers and the bindings they shadow for `end_scope` to restore
+    fn begin_scope(&mut self, ids: &[String]) -> (Vec<Reg>, Shadowed) {
+        let mut symbols = vec![];
+        let mut shadowed = vec![];
+
+        for id in ids {
+            let outer = self.seg_mut().symbols_mut().remove(id);
+            if let Some(r) = outer {
+                self.seg_mut()
+                    .symbols_mut()
+                    .insert(format!("<scoped {}>", r), r);
+            }
+
+            symbols.push(self.seg_mut().get_or_create_symbol(id.to_string()));
+            shadowed.push((id.to_string(), outer));
+        }
+
+        (symbols, shadowed)
+    }
+
+    /// Restores the bindings shadowed by `begin_scope`. Loop variables keep
+    /// their registers under hidden names, since symbols are allocated by
+    /// count and must never be removed
+    fn end_scope(&mut self, shadowed: Shadowed) {
+        let symbols = self.seg_mut().symbols_mut();
+
+        for (id, outer) in shadowed.into_iter().rev() {
+            if let Some(r) = symbols.remove(&id) {
+                symbols.insert(format!("<scoped {}>", r), r);
+            }
+            if let Some(r) = outer {
+                symbols.remove(&format!("<scoped {}>", r));
+                symbols.insert(id, r);
+            }
+        }
+    }
+
+    /// Binds the loop variables to the value in register `v`, destructuring it
+    /// when there are several, `t` is a free register in the global scope
+    fn compile_loop_targets(&mut self, symbols: &[Reg], v: Reg, t: Reg) {
+        let local = self.seg().is_local();
+
+        match symbols[..] {
+            [sym] if local => {
+                self.with(Ins::Move(sym, v));
+            }
+            [sym] => {
+                self.with(Ins::SetG(sym, v));
+            }
+            _ => {
+                let t = if local { self.seg().spare_reg() } else { t };
+                self.seg_mut().inc_slots(t + 2);
+                self.with(Ins::Unpack(v, symbols.len() as Reg));
+
+                for (i, sym) in symbols.iter().copied().enumerate() {
+                    self.load_int(t, i as i64);
+                    if local {
+                        self.with(Ins::ObjGet(sym, v, t));
+                    } else {
+                        self.with(Ins::ObjGet(t + 1, v, t))
+                            .with(Ins::SetG(sym, t + 1));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Counts from the start of a range up to but excluding its end. `continue`
+    /// jumps to the increment rather than the condition so the counter always
+    /// advances, and rebinding the loop variable does not affect iteration.
+    fn compile_for_range(
+        &mut self,
+        ids: &[String],
+        e0: &AstNode,
+        e1: &AstNode,
+        step: i64,
+        b0: &AstNode,
+    ) -> Result<&mut Self, error::Error> {
+        let label = self.seg().count();
+        let hidden: Vec<Reg> = (0..3)
+            .map(|i| {
+                self.seg_mut()
+                    .get_or_create_symbol(format!("<for {}:{}>", label, i))
+            })
+            .collect();
+
+        let local = self.seg().is_local();
+        let r = if local { hidden[0] } else { 0 };
+        self.seg_mut().inc_slots(r + 3);
+
+        self.compile_expr(r, e0)?.compile_expr(r + 1, e1)?;
+        if !local {
+            self.with(Ins::SetG(hidden[0], r))
+                .with(Ins::SetG(hidden[1], r + 1));
+        }
+
+        let jmp0 = self.seg().count();
+        if !local {
+            self.with(Ins::LoadG(r, hidden[0]))
+                .with(Ins::LoadG(r + 1, hidden[1]));
+        }
+
+        // descending ranges run while the counter is above the end instead
+        let cond = if step > 0 {
+            Ins::Lt(r + 2, r, r + 1)
+        } else {
+            Ins::Lt(r + 2, r + 1, r)
+        };
+        let jmp1 = self.with(cond).with(Ins::Nop).seg().count() - 1;
+        let (symbols, shadowed) = self.begin_scope(ids);
+        self.compile_loop_targets(&symbols, r, r + 3);
+
+        let breaks_start = self.end_jumps.len();
+        let continues_start = self.continue_jumps.len();
+        self.loop_begins.push(None);
+        self.compile_block(b0)?;
+        self.loop_begins.pop();
+        self.end_scope(shadowed);
+
+        let increment = self.seg().count();
+        if !local {
+            self.with(Ins::LoadG(r, hidden[0]));
+        }
+
+        // the counter exits the loop rather than wrap if the step overflows it
+        let jmp2 = self.load_int(r + 2, step).with(Ins::Nop).seg().count() - 1;
+        if !local {
+            self.with(Ins::SetG(hidden[0], r));
+        }
+
+        let end = self.with(Ins::Jump(jmp0)).seg().count();
+        for i in breaks_start..self.end_jumps.len() {
+            self.set_ins(self.end_jumps[i], Ins::Jump(end));
+        }
+        for i in continues_start..self.continue_jumps.len() {
+            self.set_ins(self.continue_jumps[i], Ins::Jump(increment));
+        }
+        self.end_jumps.truncate(breaks_start);
+        self.continue_jumps.truncate(continues_start);
+
+        Ok(self
+            .set_ins(jmp1, Ins::JumpFalse(r + 2, end))
+            .set_ins(jmp2, Ins::Step(r, r + 2, end)))
+    }
+
+    /// Evaluates the `while let` expression into its binding, which is scoped
+    /// to the loop body, leaving in the returned register whether the value is
+    /// non-null
+    fn compile_loop_binding(
+        &mut self,
+        id: &str,
+        e0: &AstNode,
+    ) -> Result<(Reg, Shadowed), error::Error> {
+        let r = self.seg().spare_reg();
+        self.seg_mut().inc_slots(r + 2);
+        self.compile_expr(r, e0)?;
+
+        // declared after the expression, which still sees an outer variable of
+        // the same name, and inside a function the binding may take over `r`
+        let (symbols, shadowed) = self.begin_scope(&[id.to_string()]);
+        let sym = symbols[0];
+
+        if !self.seg().is_local() {
+            self.with(Ins::SetG(sym, r));
+        } else if sym != r {
+            self.with(Ins::Move(sym, r));
+        }
+
+        self.with(Ins::LoadN(r + 1)).with(Ins::Neq(r + 1, r, r + 1));
+        Ok((r + 1, shadowed))
+    }
+
     fn compile_if(
         &mut self,
         e0: &AstNode,
@@ -289,7 +773,7 @@ impl<'a> Compiler<'a> {
             Some(b1) => match b1.ast() {
                 Ast::Block(_) => self.compile_block(b1),
                 Ast::If(a, b, c) => self.compile_if(a, b, c),
-                _ => unreachable!(),
+                _ => error::Error::invalid_ast_node(b1.pos()).err(),
             }?
             .set_ins_with_count(jmp1, &|c| Ins::Jump(c)),
         })
@@ -304,13 +788,17 @@ impl<'a> Compiler<'a> {
             Ast::Array(vs) => self.compile_array(r, vs, e.pos()),
             Ast::Deref(e0, e1) => self.compile_deref(r, e0, e1),
             Ast::Subscript(e0, e1) => self.compile_subscript(r, e0, e1),
+            Ast::Slice(e0, e1, e2) => self.compile_slice(r, e0, e1, e2),
             Ast::Call(f, args) => self.compile_call(r, f, args),
             Ast::Reference(id) => self.compile_id(r, id, e.pos()),
-            Ast::UnaryExp(op, e0) => self.compile_unary(r, *op, e0),
+            Ast::UnaryExp(op, e0) => self.compile_unary(r, *op, e0, e.pos()),
             Ast::TernaryExp(e0, e1, e2) => self.compile_ternary(r, e0, e1, e2),
             Ast::BinaryExp(op, e0, e1) => match op {
                 Op::Or | Op::And => self.compile_bool_expr(r, *op, e0, e1),
-                _ => self.compile_bin_expr(r, *op, e0, e1),
+                Op::Add if matches!(e0.ast(), Ast::BinaryExp(Op::Add, _, _)) => {
+                    self.compile_add_chain(r, e)
+                }
+                _ => self.compile_bin_expr(r, *op, e0, e1, e.pos()),
             },
             Ast::FuncDef(name, args, body) => {
                 self.compile_function(Some(r), name, args, body, e.pos())
@@ -319,7 +807,7 @@ impl<'a> Compiler<'a> {
                 self.compile_literal(r, e)
             }
             Ast::Import(s) => self.compile_import(r, s),
-            _ => unreachable!(),
+            _ => error::Error::invalid_ast_node(e.pos()).err(),
         }
     }
 
@@ -352,11 +840,31 @@ impl<'a> Compiler<'a> {
         op: Op,
         e0: &AstNode,
         e1: &AstNode,
+        pos: io::Pos,
     ) -> Result<&mut Self, error::Error> {
-        Ok(self
-            .compile_expr(r, e0)?
-            .compile_expr(r + 1, e1)?
-            .with(op.to_ins(r, r, r + 1)))
+        let ins = op
+            .to_ins(r, r, r + 1)
+            .ok_or_else(|| error::Error::invalid_ast_node(pos))?;
+
+        Ok(self.compile_expr(r, e0)?.compile_expr(r + 1, e1)?.with(ins))
+    }
+
+    /// Lowers `a + b + c ...` into a single concatenation over consecutive
+    /// registers, so string chains are built once rather than per operator
+    fn compile_add_chain(&mut self, r: Reg, e: &AstNode) -> Result<&mut Self, error::Error> {
+        let mut operands = vec![];
+        let mut head = e;
+        while let Ast::BinaryExp(Op::Add, e0, e1) = head.ast() {
+            operands.push(e1.as_ref());
+            head = e0;
+        }
+        operands.push(head);
+
+        for (i, operand) in operands.iter().rev().enumerate() {
+            self.compile_expr(r + i as Reg, operand)?;
+        }
+
+        Ok(self.with(Ins::Concat(r, r, operands.len() as Reg)))
     }
 
     fn compile_bool_expr(
@@ -372,14 +880,13 @@ impl<'a> Compiler<'a> {
         let jmp = self.seg().count();
         self.with(Ins::Nop).compile_expr(r, e1)?;
 
-        self.set_ins(
-            jmp,
-            match op {
-                Op::Or => Ins::JumpTrue(r, self.seg().count()),
-                Op::And => Ins::JumpFalse(r, self.seg().count()),
-                _ => unreachable!(),
-            },
-        );
+        let ins = match op {
+            Op::Or => Ins::JumpTrue(r, self.seg().count()),
+            Op::And => Ins::JumpFalse(r, self.seg().count()),
+            _ => return error::Error::invalid_ast_node(e0.pos()).err(),
+        };
+
+        self.set_ins(jmp, ins);
 
         for idx in (start..self.seg().count()).rev() {
             let ins = match self.seg().ins().get(idx).unwrap() {
@@ -401,24 +908,48 @@ impl<'a> Compiler<'a> {
         Ok(self)
     }
 
-    fn compile_unary(&mut self, r: Reg, op: Op, e0: &AstNode) -> Result<&mut Self, error::Error> {
-        self.compile_expr(r, e0).map(|s| {
-            s.with(match op {
-                Op::Sub => Ins::Neg(r, r),
-                Op::Not => Ins::Not(r, r),
-                Op::BitNot => Ins::BitNot(r, r),
-                _ => unreachable!(),
-            })
-        })
+    fn compile_unary(
+        &mut self,
+        r: Reg,
+        op: Op,
+        e0: &AstNode,
+        pos: io::Pos,
+    ) -> Result<&mut Self, error::Error> {
+        let ins = match op {
+            Op::Sub => Ins::Neg(r, r),
+            Op::Not => Ins::Not(r, r),
+            Op::BitNot => Ins::BitNot(r, r),
+            _ => return error::Error::invalid_ast_node(pos).err(),
+        };
+
+        self.compile_expr(r, e0).map(|s| s.with(ins))
     }
 
     fn compile_call(
         &mut self,
         r: Reg,
         f: &AstNode,
-        args: &Vec<AstNode>,
+        args: &[AstNode],
     ) -> Result<&mut Self, error::Error> {
         let argc = Reg::try_from(args.len()).unwrap();
+
+        // member calls keep the receiver next to the arguments so types with
+        // built-in methods can pass it as the first argument
+        if let Ast::Deref(e0, name) = f.ast() {
+            let k = self
+                .seg_mut()
+                .storek(Value::String(Rc::new(name.to_string())));
+            self.seg_mut().inc_slots(r + argc + 1);
+            self.compile_expr(r + 1, e0)?;
+
+            args.iter().enumerate().try_for_each(|(i, e)| {
+                self.compile_expr(r + (Reg::try_from(i).unwrap()) + 2, e)
+                    .map(|_| ())
+            })?;
+
+            return Ok(self.with(Ins::CallMethod(r, k, argc)));
+        }
+
         self.seg_mut().inc_slots(r + argc);
         self.compile_expr(r, f)?;
 
@@ -427,17 +958,26 @@ impl<'a> Compiler<'a> {
                 .map(|_| ())
         })?;
 
-        Ok(self.with(Ins::Call(r, r, r + 1)))
+        Ok(self.with(Ins::Call(r, r, r + 1, argc)))
+    }
+
+    /// Loads an integer, inline when it fits the immediate operand of `LoadI`
+    /// and from the constant pool otherwise
+    fn load_int(&mut self, r: Reg, i: i64) -> &mut Self {
+        match i16::try_from(i) {
+            Ok(i) => self.with(Ins::LoadI(r, i)),
+            Err(_) => {
+                let k = self.seg_mut().storek(Value::Int(i));
+                self.with(Ins::LoadK(r, k))
+            }
+        }
     }
 
     fn compile_literal(&mut self, r: Reg, l: &AstNode) -> Result<&mut Self, error::Error> {
         Ok(match l.ast() {
             Ast::Null => self.with(Ins::LoadN(r)),
             Ast::Bool(b) => self.with(Ins::LoadB(r, *b)),
-            Ast::Int(i) => {
-                let k = self.seg_mut().storek(Value::Int(*i));
-                self.with(Ins::LoadK(r, k))
-            }
+            Ast::Int(i) => self.load_int(r, *i),
             Ast::Float(f) => {
                 let k = self.seg_mut().storek(Value::Float(*f));
                 self.with(Ins::LoadK(r, k))
@@ -446,7 +986,7 @@ impl<'a> Compiler<'a> {
                 let k = self.seg_mut().storek(Value::String(Rc::new(s.to_string())));
                 self.with(Ins::LoadK(r, k))
             }
-            _ => unreachable!(),
+            _ => return error::Error::invalid_ast_node(l.pos()).err(),
         })
     }
 
@@ -500,12 +1040,32 @@ impl<'a> Compiler<'a> {
     fn compile_obj(
         &mut self,
         r: Reg,
-        vs: &Vec<(AstNode, AstNode)>,
+        vs: &[(AstNode, AstNode)],
     ) -> Result<&mut Self, error::Error> {
         self.seg_mut().inc_slots(r + 2);
         self.with(Ins::ObjNew(r));
 
+        // only literal keys are checked, computed and spread keys may repeat
+        let mut literal_keys = HashSet::new();
+
         for (k, v) in vs.iter() {
+            if let Ast::Spread(e0) = k.ast() {
+                self.compile_expr(r + 1, e0)?.with(Ins::Spread(r, r + 1));
+                continue;
+            }
+
+            let key = match k.ast() {
+                Ast::Null => Some(Value::Null),
+                Ast::Bool(b) => Some(Value::Bool(*b)),
+                Ast::Int(i) => Some(Value::Int(*i)),
+                Ast::Float(f) => Some(Value::Float(*f)),
+                Ast::String(s) => Some(Value::from_string(s)),
+                _ => None,
+            };
+            if let Some(key) = key.filter(|key| !literal_keys.insert(key.clone())) {
+                return error::Error::duplicate_key(&key.repr(self.env), k.pos()).err();
+            }
+
             self.compile_expr(r + 1, k)?
                 .compile_expr(r + 2, v)?
                 .with(Ins::ObjIns(r, r + 1, r + 2));
@@ -517,10 +1077,24 @@ impl<'a> Compiler<'a> {
     fn compile_array(
         &mut self,
         r: Reg,
-        vs: &Vec<AstNode>,
+        vs: &[AstNode],
         pos: io::Pos,
     ) -> Result<&mut Self, error::Error> {
         self.seg_mut().inc_slots(r + 2);
+
+        if vs.iter().any(|v| matches!(v.ast(), Ast::Spread(_))) {
+            self.with(Ins::ArrNew(r, 0));
+
+            for v in vs.iter() {
+                match v.ast() {
+                    Ast::Spread(e0) => self.compile_expr(r + 1, e0)?.with(Ins::Spread(r, r + 1)),
+                    _ => self.compile_expr(r + 1, v)?.with(Ins::ArrPush(r, r + 1)),
+                };
+            }
+
+            return Ok(self);
+        }
+
         self.with(Ins::ArrNew(r, vs.len() as u16));
 
         for (i, v) in vs.iter().enumerate() {
@@ -544,6 +1118,27 @@ impl<'a> Compiler<'a> {
         Ok(self)
     }
 
+    fn compile_slice(
+        &mut self,
+        r: Reg,
+        e0: &AstNode,
+        e1: &Option<Box<AstNode>>,
+        e2: &Option<Box<AstNode>>,
+    ) -> Result<&mut Self, error::Error> {
+        self.compile_expr(r, e0)?;
+
+        for (i, e) in [e1, e2].into_iter().enumerate() {
+            let ri = r + 1 + i as Reg;
+            match e {
+                Some(e) => self.compile_expr(ri, e)?,
+                None => self.with(Ins::LoadN(ri)),
+            };
+        }
+
+        self.seg_mut().inc_slots(r + 3);
+        Ok(self.with(Ins::Slice(r, r, r + 1)))
+    }
+
     fn compile_deref(
         &mut self,
         r: Reg,
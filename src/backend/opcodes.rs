@@ -2,6 +2,7 @@ use crate::frontend::operator::Op;
 
 pub type Reg = u16;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum Ins {
     Nop,
@@ -18,20 +19,27 @@ pub enum Ins {
     Lt(Reg, Reg, Reg),
     Shl(Reg, Reg, Reg),
     Shr(Reg, Reg, Reg),
+    Ushr(Reg, Reg, Reg),
     BitNot(Reg, Reg),
+    Concat(Reg, Reg, Reg),
     BitOr(Reg, Reg, Reg),
     BitXor(Reg, Reg, Reg),
     BitAnd(Reg, Reg, Reg),
-    Call(Reg, Reg, Reg),
+    Call(Reg, Reg, Reg, Reg),
+    CallMethod(Reg, Reg, Reg),
     Close(Reg, Reg, Reg),
     SetG(Reg, Reg),
     Move(Reg, Reg),
     LoadN(Reg),
     LoadB(Reg, bool),
+    LoadI(Reg, i16),
     LoadF(Reg, usize),
+    LoadSelf(Reg),
     LoadG(Reg, Reg),
     LoadU(Reg, Reg),
     LoadK(Reg, Reg),
+    IterNext(Reg, usize),
+    Step(Reg, Reg, usize),
     JumpFalse(Reg, usize),
     JumpTrue(Reg, usize),
     Jump(usize),
@@ -39,36 +47,100 @@ pub enum Ins {
     RetNone,
     ObjIns(Reg, Reg, Reg),
     ObjGet(Reg, Reg, Reg),
+    ObjGetStrict(Reg, Reg, Reg),
+    Slice(Reg, Reg, Reg),
     ObjNew(Reg),
     ArrNew(Reg, Reg),
+    ArrPush(Reg, Reg),
+    Spread(Reg, Reg),
+    Unpack(Reg, Reg),
     Import(Reg),
 }
 
+impl Ins {
+    /// One past the highest register the instruction reads or writes, global,
+    /// upvalue and constant operands aside
+    pub fn registers_end(&self) -> usize {
+        let end = match *self {
+            Ins::Nop | Ins::Jump(_) | Ins::RetNone => return 0,
+            Ins::Neg(a, b)
+            | Ins::Not(a, b)
+            | Ins::BitNot(a, b)
+            | Ins::Move(a, b)
+            | Ins::Step(a, b, _)
+            | Ins::ArrPush(a, b)
+            | Ins::Spread(a, b) => a.max(b),
+            Ins::Add(a, b, c)
+            | Ins::Sub(a, b, c)
+            | Ins::Mul(a, b, c)
+            | Ins::Div(a, b, c)
+            | Ins::Mod(a, b, c)
+            | Ins::Neq(a, b, c)
+            | Ins::Eq(a, b, c)
+            | Ins::Le(a, b, c)
+            | Ins::Lt(a, b, c)
+            | Ins::Shl(a, b, c)
+            | Ins::Shr(a, b, c)
+            | Ins::Ushr(a, b, c)
+            | Ins::Concat(a, b, c)
+            | Ins::BitOr(a, b, c)
+            | Ins::BitXor(a, b, c)
+            | Ins::BitAnd(a, b, c)
+            | Ins::ObjIns(a, b, c)
+            | Ins::ObjGet(a, b, c)
+            | Ins::ObjGetStrict(a, b, c) => a.max(b).max(c),
+            Ins::Slice(a, b, c) => a.max(b).max(c.saturating_add(1)),
+            Ins::Call(a, b, c, argc) => a.max(b).max(c.saturating_add(argc.saturating_sub(1))),
+            Ins::CallMethod(a, _, argc) => a.saturating_add(argc).saturating_add(1),
+            Ins::Close(a, _, c) => a.max(c.saturating_sub(1)),
+            Ins::IterNext(a, _) => a.saturating_add(2),
+            Ins::SetG(_, a)
+            | Ins::LoadN(a)
+            | Ins::LoadB(a, _)
+            | Ins::LoadI(a, _)
+            | Ins::LoadF(a, _)
+            | Ins::LoadSelf(a)
+            | Ins::LoadG(a, _)
+            | Ins::LoadU(a, _)
+            | Ins::LoadK(a, _)
+            | Ins::JumpFalse(a, _)
+            | Ins::JumpTrue(a, _)
+            | Ins::Ret(a)
+            | Ins::ObjNew(a)
+            | Ins::ArrNew(a, _)
+            | Ins::Unpack(a, _)
+            | Ins::Import(a) => a,
+        };
+        end as usize + 1
+    }
+}
+
 impl Op {
-    pub fn to_ins(&self, r0: Reg, r1: Reg, r2: Reg) -> Ins {
+    pub fn to_ins(self, r0: Reg, r1: Reg, r2: Reg) -> Option<Ins> {
         match self {
-            Op::Add => Ins::Add(r0, r1, r2),
-            Op::Sub => Ins::Sub(r0, r1, r2),
-            Op::Mul => Ins::Mul(r0, r1, r2),
-            Op::Div => Ins::Div(r0, r1, r2),
-            Op::Mod => Ins::Mod(r0, r1, r2),
-            Op::Eq => Ins::Eq(r0, r1, r2),
-            Op::Neq => Ins::Neq(r0, r1, r2),
-            Op::Le => Ins::Le(r0, r1, r2),
-            Op::Ge => Ins::Le(r0, r2, r1),
-            Op::Lt => Ins::Lt(r0, r1, r2),
-            Op::Gt => Ins::Lt(r0, r2, r1),
-            Op::Shr => Ins::Shr(r0, r1, r2),
-            Op::Shl => Ins::Shl(r0, r1, r2),
-            Op::BitOr => Ins::BitOr(r0, r1, r2),
-            Op::BitXor => Ins::BitXor(r0, r1, r2),
-            Op::BitAnd => Ins::BitAnd(r0, r1, r2),
-            Op::AddEq => Ins::Add(r0, r1, r2),
-            Op::SubEq => Ins::Sub(r0, r1, r2),
-            Op::MulEq => Ins::Mul(r0, r1, r2),
-            Op::DivEq => Ins::Div(r0, r1, r2),
-            Op::ModEq => Ins::Mod(r0, r1, r2),
-            Op::Or | Op::And | Op::Not | Op::BitNot | Op::Assign => unreachable!(),
+            Op::Add => Some(Ins::Add(r0, r1, r2)),
+            Op::Sub => Some(Ins::Sub(r0, r1, r2)),
+            Op::Mul => Some(Ins::Mul(r0, r1, r2)),
+            Op::Div => Some(Ins::Div(r0, r1, r2)),
+            Op::Mod => Some(Ins::Mod(r0, r1, r2)),
+            Op::Eq => Some(Ins::Eq(r0, r1, r2)),
+            Op::Neq => Some(Ins::Neq(r0, r1, r2)),
+            Op::Le => Some(Ins::Le(r0, r1, r2)),
+            Op::Ge => Some(Ins::Le(r0, r2, r1)),
+            Op::Lt => Some(Ins::Lt(r0, r1, r2)),
+            Op::Gt => Some(Ins::Lt(r0, r2, r1)),
+            Op::Shr => Some(Ins::Shr(r0, r1, r2)),
+            Op::Ushr => Some(Ins::Ushr(r0, r1, r2)),
+            Op::Shl => Some(Ins::Shl(r0, r1, r2)),
+            Op::BitOr => Some(Ins::BitOr(r0, r1, r2)),
+            Op::BitXor => Some(Ins::BitXor(r0, r1, r2)),
+            Op::BitAnd => Some(Ins::BitAnd(r0, r1, r2)),
+            Op::AddEq => Some(Ins::Add(r0, r1, r2)),
+            Op::SubEq => Some(Ins::Sub(r0, r1, r2)),
+            Op::MulEq => Some(Ins::Mul(r0, r1, r2)),
+            Op::DivEq => Some(Ins::Div(r0, r1, r2)),
+            Op::ModEq => Some(Ins::Mod(r0, r1, r2)),
+            Op::Or | Op::And | Op::Not | Op::BitNot | Op::Assign => None,
         }
     }
 }
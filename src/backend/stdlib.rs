@@ -1,13 +1,17 @@
 use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    hash::BuildHasher,
     rc::Rc,
     time::{SystemTime, UNIX_EPOCH},
 };
 
 use crate::{
     error,
+    frontend::operator::Op,
     vm::{
-        heap::{Alloc, HeapNode},
-        Env, ModuleFnRecord, Value,
+        heap::{Alloc, Heap, HeapNode, ObjectMap},
+        Env, ModuleFnRecord, Segment, Value,
     },
 };
 
@@ -19,21 +23,57 @@ fn assert_arg_count(_env: &Env, rec: usize, exp: usize) -> Result<(), error::Err
     }
 }
 
-fn std_println(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+fn assert_arg_range(_env: &Env, rec: usize, min: usize, max: usize) -> Result<(), error::Error> {
+    if rec < min {
+        error::Error::argument_error(rec as u32, min as u32).err()
+    } else if rec > max {
+        error::Error::argument_error(rec as u32, max as u32).err()
+    } else {
+        Ok(())
+    }
+}
+
+fn write_value(
+    env: &mut Env,
+    arg0: usize,
+    argc: usize,
+    to_stderr: bool,
+    end: &str,
+) -> Result<Value, error::Error> {
     assert_arg_count(env, argc, 1)?;
-    println!("{}", env.reg(arg0).to_string(env));
+    let s = env.to_display_string(&env.reg(arg0).clone())?;
+    let out = if to_stderr {
+        env.stderr()
+    } else {
+        env.stdout()
+    };
+
+    write!(out, "{}{}", s, end).map_err(|e| error::Error::write_error(&e.to_string()))?;
     Ok(Value::Null)
 }
 
+fn std_println(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    write_value(env, arg0, argc, false, "\n")
+}
+
 fn std_print(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
-    assert_arg_count(env, argc, 1)?;
-    print!("{}", env.reg(arg0).to_string(env));
-    Ok(Value::Null)
+    write_value(env, arg0, argc, false, "")
+}
+
+fn std_eprintln(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    write_value(env, arg0, argc, true, "\n")
+}
+
+fn std_eprint(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    write_value(env, arg0, argc, true, "")
 }
 
 fn std_typeof(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
     assert_arg_count(env, argc, 1)?;
-    Ok(Value::from_string(env.reg(arg0).type_name()))
+    match env.reg(arg0) {
+        Value::Object(p) if env.module_name(*p).is_some() => Ok(Value::from_string("Module")),
+        v => Ok(Value::from_string(v.type_name())),
+    }
 }
 
 fn std_len(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
@@ -41,9 +81,101 @@ fn std_len(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Erro
     env.reg(arg0).length(env).map(|len| Value::Int(len as i64))
 }
 
+fn std_is_empty(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    env.reg(arg0).length(env).map(|len| Value::Bool(len == 0))
+}
+
+fn std_arity(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    match env.reg(arg0) {
+        Value::Func(fid, _) => Ok(Value::Int(env.get_segment(*fid as usize).arity() as i64)),
+        v => error::Error::type_error(&Value::Func(0, 0), v).err(),
+    }
+}
+
 fn std_str(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
     assert_arg_count(env, argc, 1)?;
-    Ok(Value::String(Rc::new(env.reg(arg0).to_string(env))))
+    let s = env.to_display_string(&env.reg(arg0).clone())?;
+    Ok(Value::String(Rc::new(s)))
+}
+
+fn std_repr(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    Ok(Value::String(Rc::new(env.reg(arg0).repr(env))))
+}
+
+fn std_summary(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_range(env, argc, 1, 2)?;
+    let limit = match argc {
+        1 => 10,
+        _ => match env.reg(arg0 + 1) {
+            Value::Int(n) if *n >= 0 => *n as usize,
+            Value::Int(n) => return error::Error::invalid_limit(*n).err(),
+            v => return error::Error::type_error(&Value::Int(0), v).err(),
+        },
+    };
+
+    Ok(Value::String(Rc::new(env.reg(arg0).summary(env, limit))))
+}
+
+/// Hashes a primitive with the hasher backing object maps, containers and
+/// functions are refused as their hashes only reflect identity
+fn std_hash(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    match env.reg(arg0) {
+        v @ (Value::Null | Value::Int(_) | Value::Float(_) | Value::Bool(_) | Value::String(_)) => {
+            Ok(Value::Int(ObjectMap::default().hasher().hash_one(v) as i64))
+        }
+        v => error::Error::unhashable_type(v).err(),
+    }
+}
+
+/// Wraps a single argument function so results are cached by argument in an
+/// object captured by the wrapper, whose native segment `memoize` captures
+fn std_memoize(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    let f = match env.reg(arg0) {
+        f @ Value::Func(..) => f.clone(),
+        v => return error::Error::type_error(&Value::Func(0, 0), v).err(),
+    };
+
+    let fid = match env.captured() {
+        [Value::Func(fid, _)] => *fid as usize,
+        _ => unreachable!("memoize without its wrapper segment"),
+    };
+
+    env.maybe_collect();
+    let cache = Value::Object(env.heap.allocate(HeapNode::object(ObjectMap::default())));
+    Ok(env.new_native_closure(fid, vec![f, cache]))
+}
+
+fn std_memoized_call(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    let key = match env.reg(arg0) {
+        v @ (Value::Null | Value::Int(_) | Value::Float(_) | Value::Bool(_) | Value::String(_)) => {
+            v.clone()
+        }
+        v => return error::Error::unhashable_type(v).err(),
+    };
+
+    let (f, cache) = match env.captured() {
+        [f, Value::Object(cache)] => (f.clone(), *cache),
+        _ => unreachable!("memoized function without its cache"),
+    };
+
+    if let HeapNode::Object { mark: _, map } = env.heap.access(cache) {
+        if let Some(v) = map.get(&key) {
+            return Ok(v.clone());
+        }
+    }
+
+    let v = env.call_function(&f, std::slice::from_ref(&key))?;
+    match env.heap.access_mut(cache) {
+        HeapNode::Object { mark: _, map } => map.insert(key, v.clone()),
+        _ => unreachable!("value-pointer heap-object type mismatch"),
+    };
+    Ok(v)
 }
 
 fn std_array_append(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
@@ -56,16 +188,75 @@ fn std_array_append(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, er
         },
         v => error::Error::type_error(&Value::Array(0), v).err()?,
     }
-    Ok(Value::Null)
+    Ok(env.reg(arg0).clone())
 }
 
-fn std_array_pop(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+/// Stable merge sort by the ordering of the comparison operators, failing on
+/// elements that cannot be compared
+fn sort_values(mut values: Vec<Value>, heap: &Heap) -> Result<Vec<Value>, error::Error> {
+    if values.len() <= 1 {
+        return Ok(values);
+    }
+
+    let right = values.split_off(values.len() / 2);
+    let (left, right) = (sort_values(values, heap)?, sort_values(right, heap)?);
+
+    let mut sorted = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        if r.compare(l, Op::Lt, heap)? == Some(Ordering::Less) {
+            sorted.extend(right.next());
+        } else {
+            sorted.extend(left.next());
+        }
+    }
+
+    sorted.extend(left.chain(right));
+    Ok(sorted)
+}
+
+fn std_sort(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    let arr = match env.reg(arg0) {
+        Value::Array(arr) => *arr,
+        v => return error::Error::type_error(&Value::Array(0), v).err(),
+    };
+
+    let values = match env.heap.access(arr) {
+        HeapNode::Array { mark: _, vec } => vec.clone(),
+        _ => unreachable!("value-pointer heap-object type mismatch"),
+    };
+    let sorted = sort_values(values, &env.heap)?;
+
+    match env.heap.access_mut(arr) {
+        HeapNode::Array { mark: _, vec } => *vec = sorted,
+        _ => unreachable!("value-pointer heap-object type mismatch"),
+    }
+    Ok(env.reg(arg0).clone())
+}
+
+fn std_reverse(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
     assert_arg_count(env, argc, 1)?;
     match env.reg(arg0) {
         Value::Array(arr) => match env.heap.access_mut(*arr) {
-            HeapNode::Array { mark: _, vec } => {
-                vec.pop().ok_or(error::Error::array_length_error(0))
-            }
+            HeapNode::Array { mark: _, vec } => vec.reverse(),
+            _ => unreachable!("value-pointer heap-object type mismatch"),
+        },
+        v => error::Error::type_error(&Value::Array(0), v).err()?,
+    }
+    Ok(env.reg(arg0).clone())
+}
+
+/// Pops the last element, or returns the optional default for an empty array
+fn std_array_pop(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_range(env, argc, 1, 2)?;
+    let default = (argc == 2).then(|| env.reg(arg0 + 1).clone());
+    match env.reg(arg0) {
+        Value::Array(arr) => match env.heap.access_mut(*arr) {
+            HeapNode::Array { mark: _, vec } => vec
+                .pop()
+                .or(default)
+                .ok_or(error::Error::array_length_error(0)),
             _ => unreachable!("value-pointer heap-object type mismatch"),
         },
         v => error::Error::type_error(&Value::Array(0), v).err(),
@@ -81,50 +272,73 @@ fn std_insert(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::E
             HeapNode::Array { mark: _, vec } => match key {
                 Value::Int(i) if 0 <= i && (i as usize) < vec.len() => {
                     vec.insert(i as usize, val);
-                    Ok(Value::Null)
                 }
-                Value::Int(i) => error::Error::array_index_error(i as u32).err(),
-                v => error::Error::type_error(&Value::Int(0), &v).err(),
+                Value::Int(i) => error::Error::array_index_error(i as u32).err()?,
+                v => error::Error::type_error(&Value::Int(0), &v).err()?,
             },
             _ => unreachable!("value-pointer heap-object type mismatch"),
         },
         Value::Object(p) => match env.heap.access_mut(*p) {
             HeapNode::Object { mark: _, map } => {
                 map.insert(key, val);
-                Ok(Value::Null)
             }
             _ => unreachable!("value-pointer heap-object type mismatch"),
         },
-        v => error::Error::type_error_any(v).err(),
+        v => error::Error::type_error_any(v).err()?,
     }
+    Ok(env.reg(arg0).clone())
 }
 
+/// Removes an array index or object key, an optional default is returned for
+/// an index out of range or an absent key
 fn std_remove(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
-    assert_arg_count(env, argc, 2)?;
+    assert_arg_range(env, argc, 2, 3)?;
     let key = env.reg(arg0 + 1).clone();
+    let default = (argc == 3).then(|| env.reg(arg0 + 2).clone());
     match env.reg(arg0) {
         Value::Array(p) => match env.heap.access_mut(*p) {
             HeapNode::Array { mark: _, vec } => match key {
                 Value::Int(i) if 0 <= i && (i as usize) < vec.len() => Ok(vec.remove(i as usize)),
-                Value::Int(i) => error::Error::array_index_error(i as u32).err(),
+                Value::Int(i) => default.ok_or(error::Error::array_index_error(i as u32)),
                 v => error::Error::type_error(&Value::Int(0), &v).err(),
             },
             _ => unreachable!("value-pointer heap-object type mismatch"),
         },
         Value::Object(p) => match env.heap.access_mut(*p) {
-            HeapNode::Object { mark: _, map } => Ok(map.remove(&key).unwrap_or(Value::Null)),
+            HeapNode::Object { mark: _, map } => {
+                Ok(map.remove(&key).or(default).unwrap_or(Value::Null))
+            }
             _ => unreachable!("value-pointer heap-object type mismatch"),
         },
         v => error::Error::type_error_any(v).err(),
     }
 }
 
+fn std_take(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 2)?;
+    let key = env.reg(arg0 + 1).clone();
+    match env.reg(arg0) {
+        Value::Object(p) => match env.heap.access_mut(*p) {
+            HeapNode::Object { mark: _, map } => match map.remove(&key) {
+                Some(v) => Ok(v),
+                None => {
+                    let key_repr = key.repr(env);
+                    error::Error::missing_key(key, &key_repr).err()
+                }
+            },
+            _ => unreachable!("value-pointer heap-object type mismatch"),
+        },
+        v => error::Error::type_error(&Value::Object(0), v).err(),
+    }
+}
+
 fn std_object_keys(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
     assert_arg_count(env, argc, 1)?;
+    env.maybe_collect();
     match env.reg(arg0) {
         Value::Object(p) => match env.heap.access_mut(*p) {
             HeapNode::Object { mark: _, map } => {
-                let keys = map.keys().map(|v| v.clone()).collect();
+                let keys = map.keys().cloned().collect();
                 Ok(Value::Array(env.heap.allocate(HeapNode::array(keys))))
             }
             _ => unreachable!("value-pointer heap-object type mismatch"),
@@ -133,6 +347,215 @@ fn std_object_keys(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, err
     }
 }
 
+fn object_map(env: &Env, ptr: usize) -> &ObjectMap {
+    match env.heap.access(ptr) {
+        HeapNode::Object { mark: _, map } => map,
+        _ => unreachable!("value-pointer heap-object type mismatch"),
+    }
+}
+
+/// Copies `a` and overlays the entries of `b`; when `deep`, entries holding
+/// objects on both sides are merged recursively unless that pair of objects
+/// is already being merged further up, in which case `b` wins
+fn merge_objects(
+    env: &mut Env,
+    a: usize,
+    b: usize,
+    deep: bool,
+    merging: &mut HashSet<(usize, usize)>,
+) -> usize {
+    let mut map = object_map(env, a).clone();
+
+    for (k, v) in object_map(env, b).clone() {
+        let merged = match (map.get(&k), &v) {
+            (Some(Value::Object(pa)), Value::Object(pb)) if deep && merging.insert((*pa, *pb)) => {
+                let pair = (*pa, *pb);
+                let ptr = merge_objects(env, pair.0, pair.1, deep, merging);
+                merging.remove(&pair);
+                Value::Object(ptr)
+            }
+            _ => v,
+        };
+        map.insert(k, merged);
+    }
+
+    env.heap.allocate(HeapNode::object(map))
+}
+
+fn merge_args(env: &mut Env, arg0: usize, argc: usize, deep: bool) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 2)?;
+    let (a, b) = match (env.reg(arg0), env.reg(arg0 + 1)) {
+        (Value::Object(a), Value::Object(b)) => (*a, *b),
+        (Value::Object(_), v) | (v, _) => {
+            return error::Error::type_error(&Value::Object(0), v).err();
+        }
+    };
+
+    // both arguments stay rooted in their registers while the copies are built
+    env.maybe_collect();
+    let mut merging = HashSet::from([(a, b)]);
+    Ok(Value::Object(merge_objects(env, a, b, deep, &mut merging)))
+}
+
+fn std_merge(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    merge_args(env, arg0, argc, false)
+}
+
+fn std_deep_merge(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    merge_args(env, arg0, argc, true)
+}
+
+fn flatten_into(env: &Env, out: &mut Vec<Value>, v: Value) {
+    match v {
+        Value::Array(p) => match env.heap.access(p) {
+            HeapNode::Array { mark: _, vec } => out.extend(vec.iter().cloned()),
+            _ => unreachable!("value-pointer heap-object type mismatch"),
+        },
+        v => out.push(v),
+    }
+}
+
+fn std_flatten(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    env.maybe_collect();
+    match env.reg(arg0) {
+        Value::Array(p) => match env.heap.access(*p) {
+            HeapNode::Array { mark: _, vec } => {
+                let mut out = vec![];
+                vec.iter()
+                    .for_each(|v| flatten_into(env, &mut out, v.clone()));
+                Ok(Value::Array(env.heap.allocate(HeapNode::array(out))))
+            }
+            _ => unreachable!("value-pointer heap-object type mismatch"),
+        },
+        v => error::Error::type_error(&Value::Array(0), v).err(),
+    }
+}
+
+/// Pairs up the elements of each array by index, stopping at the shortest
+fn std_zip(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_range(env, argc, 1, usize::MAX)?;
+    let mut arrays = vec![];
+    for i in arg0..arg0 + argc {
+        match env.reg(i) {
+            Value::Array(p) => match env.heap.access(*p) {
+                HeapNode::Array { mark: _, vec } => arrays.push(vec.clone()),
+                _ => unreachable!("value-pointer heap-object type mismatch"),
+            },
+            v => return error::Error::type_error(&Value::Array(0), v).err(),
+        }
+    }
+
+    env.maybe_collect();
+    let len = arrays.iter().map(|a| a.len()).min().unwrap_or(0);
+    let pairs = (0..len)
+        .map(|i| {
+            let group = arrays.iter().map(|a| a[i].clone()).collect();
+            Value::Array(env.heap.allocate(HeapNode::array(group)))
+        })
+        .collect();
+
+    Ok(Value::Array(env.heap.allocate(HeapNode::array(pairs))))
+}
+
+/// Gathers the output of a callback, given the element and the callback's result
+type Collect = fn(&mut Env, Value, Value, &mut Vec<Value>) -> Result<(), error::Error>;
+
+/// Applies a callback to each array element, the callback may call back into
+/// natives and allocate freely as the output array is rooted for the duration
+fn map_array(
+    env: &mut Env,
+    arg0: usize,
+    argc: usize,
+    collect: Collect,
+) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 2)?;
+    let p = match env.reg(arg0) {
+        Value::Array(p) => *p,
+        v => return error::Error::type_error(&Value::Array(0), v).err(),
+    };
+
+    let f = env.reg(arg0 + 1).clone();
+    env.maybe_collect();
+    let out = Value::Array(env.heap.allocate(HeapNode::array(vec![])));
+
+    env.push_root(out.clone());
+    let result = map_into(env, p, &f, &out, collect);
+    env.pop_root();
+
+    result.map(|_| out)
+}
+
+fn map_into(
+    env: &mut Env,
+    p: usize,
+    f: &Value,
+    out: &Value,
+    collect: Collect,
+) -> Result<(), error::Error> {
+    let mut i = 0;
+    loop {
+        // elements are read by index as the callback may modify the input array
+        let v = match env.heap.access(p) {
+            HeapNode::Array { mark: _, vec } => match vec.get(i) {
+                Some(v) => v.clone(),
+                None => return Ok(()),
+            },
+            _ => unreachable!("value-pointer heap-object type mismatch"),
+        };
+
+        let r = env.call_function(f, std::slice::from_ref(&v))?;
+
+        // the element and result are only held here while collecting, which
+        // may call back into a hook such as `__bool__`
+        env.push_root(v.clone());
+        env.push_root(r.clone());
+        let mut items = vec![];
+        let result = collect(env, v, r, &mut items);
+        env.pop_root();
+        env.pop_root();
+        result?;
+
+        if let Value::Array(q) = out {
+            match env.heap.access_mut(*q) {
+                HeapNode::Array { mark: _, vec } => vec.extend(items),
+                _ => unreachable!("value-pointer heap-object type mismatch"),
+            }
+        }
+
+        i += 1;
+    }
+}
+
+fn std_map(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    map_array(env, arg0, argc, |_, _, r, out| {
+        out.push(r);
+        Ok(())
+    })
+}
+
+fn std_flat_map(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    map_array(env, arg0, argc, |env, _, r, out| {
+        flatten_into(env, out, r);
+        Ok(())
+    })
+}
+
+fn std_filter(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    map_array(env, arg0, argc, |env, v, r, out| {
+        if env.truthy(&r)? {
+            out.push(v)
+        }
+        Ok(())
+    })
+}
+
+fn std_error(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    let msg = env.to_display_string(&env.reg(arg0).clone())?;
+    error::Error::custom_error(&msg).err()
+}
+
 fn std_time(_env: &mut Env, _arg0: usize, _argc: usize) -> Result<Value, error::Error> {
     let millis = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -141,12 +564,494 @@ fn std_time(_env: &mut Env, _arg0: usize, _argc: usize) -> Result<Value, error::
     Ok(Value::Int(millis as i64))
 }
 
-fn std_parse_int(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+fn std_stack_depth(env: &mut Env, _arg0: usize, _argc: usize) -> Result<Value, error::Error> {
+    Ok(Value::Int(env.call_depth() as i64))
+}
+
+fn std_floor_mod(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 2)?;
+    let divisor = env.reg(arg0 + 1);
+    match ((env.reg(arg0) % divisor)?, divisor) {
+        (Value::Int(r), Value::Int(d)) if r != 0 && (r < 0) != (*d < 0) => Ok(Value::Int(r + d)),
+        (Value::Float(r), Value::Int(d)) if r != 0.0 && (r < 0.0) != (*d < 0) => {
+            Ok(Value::Float(r + *d as f64))
+        }
+        (Value::Float(r), Value::Float(d)) if r != 0.0 && (r < 0.0) != (*d < 0.0) => {
+            Ok(Value::Float(r + d))
+        }
+        (r, _) => Ok(r),
+    }
+}
+
+fn round_with(
+    env: &mut Env,
+    arg0: usize,
+    argc: usize,
+    f: fn(f64) -> f64,
+) -> Result<Value, error::Error> {
+    assert_arg_range(env, argc, 1, 2)?;
+    let x = match env.reg(arg0) {
+        Value::Int(i) if argc == 1 => return Ok(Value::Int(*i)),
+        v => match v.as_f64() {
+            Some(x) => x,
+            None => return error::Error::type_error(&Value::Float(0.0), v).err(),
+        },
+    };
+
+    match argc {
+        1 if x.is_finite() => Ok(Value::Int(f(x) as i64)),
+        1 => Ok(Value::Float(x)),
+        _ => match env.reg(arg0 + 1) {
+            // beyond this a power of ten is not a finite float
+            Value::Int(n) if n.unsigned_abs() > f64::MAX_10_EXP as u64 => {
+                error::Error::invalid_precision(*n).err()
+            }
+            Value::Int(n) if *n >= 0 => {
+                let scale = 10f64.powi(*n as i32);
+                Ok(Value::Float(f(x * scale) / scale))
+            }
+            Value::Int(n) => {
+                let scale = 10f64.powi(-*n as i32);
+                Ok(Value::Float(f(x / scale) * scale))
+            }
+            v => error::Error::type_error(&Value::Int(0), v).err(),
+        },
+    }
+}
+
+fn std_round(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    round_with(env, arg0, argc, f64::round)
+}
+
+fn std_round_half_even(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    round_with(env, arg0, argc, f64::round_ties_even)
+}
+
+fn std_abs(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
     assert_arg_count(env, argc, 1)?;
     match env.reg(arg0) {
-        Value::String(s) => match s.parse().into() {
-            Ok(i) => Ok(Value::Int(i)),
-            Err(_) => error::Error::invalid_string_parse_input(s).err(),
+        Value::Int(i) => match i.checked_abs() {
+            Some(i) => Ok(Value::Int(i)),
+            None => error::Error::integer_overflow(*i).err(),
+        },
+        Value::Float(f) => Ok(Value::Float(f.abs())),
+        v => error::Error::type_error(&Value::Float(0.0), v).err(),
+    }
+}
+
+fn std_sign(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    match env.reg(arg0).as_f64() {
+        Some(x) if x > 0.0 => Ok(Value::Int(1)),
+        Some(x) if x < 0.0 => Ok(Value::Int(-1)),
+        Some(_) => Ok(Value::Int(0)),
+        None => error::Error::type_error(&Value::Float(0.0), env.reg(arg0)).err(),
+    }
+}
+
+/// Constrains a number to the inclusive range, returning whichever of the
+/// three arguments applies unchanged
+fn std_clamp(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 3)?;
+    let mut bounds = [0.0; 3];
+    for (i, bound) in bounds.iter_mut().enumerate() {
+        *bound = match env.reg(arg0 + i).as_f64() {
+            Some(x) => x,
+            None => return error::Error::type_error(&Value::Float(0.0), env.reg(arg0 + i)).err(),
+        };
+    }
+
+    let [x, lo, hi] = bounds;
+    if lo > hi {
+        let (lo, hi) = (env.reg(arg0 + 1).repr(env), env.reg(arg0 + 2).repr(env));
+        return error::Error::invalid_bounds(&lo, &hi).err();
+    }
+
+    Ok(match x {
+        x if x < lo => env.reg(arg0 + 1),
+        x if x > hi => env.reg(arg0 + 2),
+        _ => env.reg(arg0),
+    }
+    .clone())
+}
+
+fn extreme(values: &[Value], want: Ordering, heap: &Heap) -> Result<Value, error::Error> {
+    let mut best = match values.first() {
+        Some(v @ (Value::Int(_) | Value::Float(_) | Value::String(_))) => v,
+        Some(v) => return error::Error::type_error_any(v).err(),
+        None => return error::Error::array_length_error(0).err(),
+    };
+
+    // ordered as by the comparison operators, so Ints and Floats mix
+    let op = if want == Ordering::Less {
+        Op::Lt
+    } else {
+        Op::Gt
+    };
+    for v in &values[1..] {
+        match v.compare(best, op, heap)? {
+            Some(o) if o == want => best = v,
+            Some(_) => {}
+            None => return error::Error::type_error(best, v).err(),
+        }
+    }
+
+    Ok(best.clone())
+}
+
+/// Reduces either the arguments or, when given a single array, its elements
+fn std_extreme(env: &Env, arg0: usize, argc: usize, want: Ordering) -> Result<Value, error::Error> {
+    assert_arg_range(env, argc, 1, usize::MAX)?;
+    match env.reg(arg0) {
+        Value::Array(p) if argc == 1 => match env.heap.access(*p) {
+            HeapNode::Array { mark: _, vec } => extreme(vec, want, &env.heap),
+            _ => unreachable!("value-pointer heap-object type mismatch"),
+        },
+        _ => extreme(
+            &(arg0..arg0 + argc)
+                .map(|i| env.reg(i).clone())
+                .collect::<Vec<_>>(),
+            want,
+            &env.heap,
+        ),
+    }
+}
+
+fn std_min(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    std_extreme(env, arg0, argc, Ordering::Less)
+}
+
+fn std_max(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    std_extreme(env, arg0, argc, Ordering::Greater)
+}
+
+fn std_to_fixed(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 2)?;
+    let x = match env.reg(arg0).as_f64() {
+        Some(x) => x,
+        None => return error::Error::type_error(&Value::Float(0.0), env.reg(arg0)).err(),
+    };
+
+    match env.reg(arg0 + 1) {
+        Value::Int(n) if *n < 0 => error::Error::invalid_precision(*n).err(),
+        Value::Int(n) => Ok(Value::String(Rc::new(format!("{:.*}", *n as usize, x)))),
+        v => error::Error::type_error(&Value::Int(0), v).err(),
+    }
+}
+
+/// Substitutes `{}` with the next argument, `{0}` with an argument by position
+/// and `{name}` with a key of the object given as the first argument, doubled
+/// braces are kept literally
+fn std_format(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_range(env, argc, 1, usize::MAX)?;
+    let template = match env.reg(arg0) {
+        Value::String(s) => s.clone(),
+        v => return error::Error::type_error(&Value::String(Rc::new(String::new())), v).err(),
+    };
+
+    let args = arg0 + 1..arg0 + argc;
+    let mut out = String::new();
+    let mut next = 0;
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let name = match rest.find('}') {
+                    Some(end) => &rest[..end],
+                    None => return error::Error::invalid_placeholder(rest).err(),
+                };
+                chars = rest[name.len() + 1..].chars();
+
+                let value = if name.is_empty() {
+                    next += 1;
+                    args.clone().nth(next - 1).map(|r| env.reg(r).clone())
+                } else if let Ok(i) = name.parse::<usize>() {
+                    args.clone().nth(i).map(|r| env.reg(r).clone())
+                } else {
+                    match args.clone().next().map(|r| env.reg(r)) {
+                        Some(Value::Object(p)) => match env.heap.access(*p) {
+                            HeapNode::Object { mark: _, map } => {
+                                map.get(&Value::from_string(name)).cloned()
+                            }
+                            _ => unreachable!("value-pointer heap-object type mismatch"),
+                        },
+                        _ => None,
+                    }
+                };
+
+                match value {
+                    Some(v) => out.push_str(&env.to_display_string(&v)?),
+                    None => return error::Error::invalid_placeholder(name).err(),
+                }
+            }
+            c => out.push(c),
+        }
+    }
+
+    Ok(Value::String(Rc::new(out)))
+}
+
+fn format_radix(
+    env: &mut Env,
+    arg0: usize,
+    argc: usize,
+    radix: u32,
+) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    match env.reg(arg0) {
+        Value::Int(i) => {
+            let digits = match radix {
+                16 => format!("{:x}", i.unsigned_abs()),
+                _ => format!("{:b}", i.unsigned_abs()),
+            };
+            let sign = if *i < 0 { "-" } else { "" };
+            Ok(Value::String(Rc::new(format!("{}{}", sign, digits))))
+        }
+        v => error::Error::type_error(&Value::Int(0), v).err(),
+    }
+}
+
+fn std_to_hex(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    format_radix(env, arg0, argc, 16)
+}
+
+fn std_to_bin(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    format_radix(env, arg0, argc, 2)
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn map_string(
+    env: &mut Env,
+    arg0: usize,
+    argc: usize,
+    f: fn(&str) -> String,
+) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    match env.reg(arg0) {
+        Value::String(s) => Ok(Value::String(Rc::new(f(s)))),
+        v => error::Error::type_error(&Value::String(Rc::default()), v).err(),
+    }
+}
+
+fn std_to_upper(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    map_string(env, arg0, argc, str::to_uppercase)
+}
+
+fn std_to_lower(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    map_string(env, arg0, argc, str::to_lowercase)
+}
+
+fn std_capitalize(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    map_string(env, arg0, argc, capitalize)
+}
+
+fn std_title(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    map_string(env, arg0, argc, |s| {
+        s.split_inclusive(char::is_whitespace)
+            .map(capitalize)
+            .collect()
+    })
+}
+
+fn split_string(
+    env: &mut Env,
+    arg0: usize,
+    argc: usize,
+    f: fn(&str) -> Vec<&str>,
+) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    let parts = match env.reg(arg0) {
+        Value::String(s) => f(s).into_iter().map(Value::from_string).collect(),
+        v => return error::Error::type_error(&Value::String(Rc::default()), v).err(),
+    };
+
+    env.maybe_collect();
+    Ok(Value::Array(env.heap.allocate(HeapNode::array(parts))))
+}
+
+/// Splits on a separator, or into characters when it is empty. An optional
+/// limit caps the number of parts, leaving the remainder unsplit in the last
+/// one; a negative limit is unlimited.
+fn std_split(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_range(env, argc, 2, 3)?;
+    let limit = match (argc == 3).then(|| env.reg(arg0 + 2)) {
+        None => None,
+        Some(Value::Int(n)) => usize::try_from(*n).ok(),
+        Some(v) => return error::Error::type_error(&Value::Int(0), v).err(),
+    };
+
+    let parts: Vec<Value> = match (env.reg(arg0), env.reg(arg0 + 1)) {
+        (Value::String(_), Value::String(_)) if limit == Some(0) => vec![],
+        (Value::String(s), Value::String(sep)) if sep.is_empty() => {
+            let n = limit.map_or(usize::MAX, |n| n - 1);
+            let mut parts: Vec<Value> = s
+                .chars()
+                .take(n)
+                .map(|c| Value::String(Rc::new(c.to_string())))
+                .collect();
+            let rest: String = s.chars().skip(n).collect();
+            if !rest.is_empty() {
+                parts.push(Value::String(Rc::new(rest)));
+            }
+            parts
+        }
+        (Value::String(s), Value::String(sep)) => match limit {
+            Some(n) => s.splitn(n, sep.as_str()).map(Value::from_string).collect(),
+            None => s.split(sep.as_str()).map(Value::from_string).collect(),
+        },
+        (Value::String(_), v) | (v, _) => {
+            return error::Error::type_error(&Value::String(Rc::default()), v).err()
+        }
+    };
+
+    env.maybe_collect();
+    Ok(Value::Array(env.heap.allocate(HeapNode::array(parts))))
+}
+
+fn std_split_whitespace(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    split_string(env, arg0, argc, |s| s.split_whitespace().collect())
+}
+
+fn std_lines(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    split_string(env, arg0, argc, |s| s.lines().collect())
+}
+
+fn std_char_code_at(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 2)?;
+    match (env.reg(arg0), env.reg(arg0 + 1)) {
+        (Value::String(s), Value::Int(i)) => usize::try_from(*i)
+            .ok()
+            .and_then(|i| s.chars().nth(i))
+            .map(|c| Value::Int(c as i64))
+            .ok_or(error::Error::array_index_error(*i as u32)),
+        (Value::String(_), v) => error::Error::type_error(&Value::Int(0), v).err(),
+        (v, _) => error::Error::type_error(&Value::String(Rc::default()), v).err(),
+    }
+}
+
+fn std_code_points(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    let points = match env.reg(arg0) {
+        Value::String(s) => s.chars().map(|c| Value::Int(c as i64)).collect(),
+        v => return error::Error::type_error(&Value::String(Rc::default()), v).err(),
+    };
+
+    env.maybe_collect();
+    Ok(Value::Array(env.heap.allocate(HeapNode::array(points))))
+}
+
+fn std_repeat(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 2)?;
+    match (env.reg(arg0), env.reg(arg0 + 1)) {
+        (Value::String(s), Value::Int(n)) => {
+            Ok(Value::String(Rc::new(s.repeat((*n).max(0) as usize))))
+        }
+        (Value::String(_), v) => error::Error::type_error(&Value::Int(0), v).err(),
+        (v, _) => error::Error::type_error(&Value::String(Rc::default()), v).err(),
+    }
+}
+
+/// Pads a string with a single fill character up to `width` chars, on the
+/// left or the right. Strings already as wide are returned unchanged.
+fn pad(env: &mut Env, arg0: usize, argc: usize, left: bool) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 3)?;
+    let (s, width, fill) = match (env.reg(arg0), env.reg(arg0 + 1), env.reg(arg0 + 2)) {
+        (Value::String(s), Value::Int(w), Value::String(f)) => (s, *w, f),
+        (Value::String(_), Value::Int(_), v) | (v, _, _) if !matches!(v, Value::String(_)) => {
+            return error::Error::type_error(&Value::String(Rc::default()), v).err();
+        }
+        (_, v, _) => return error::Error::type_error(&Value::Int(0), v).err(),
+    };
+
+    let mut chars = fill.chars();
+    let fill = match (chars.next(), chars.next()) {
+        (Some(c), None) => c,
+        _ => return error::Error::invalid_fill(fill).err(),
+    };
+
+    let len = s.chars().count();
+    let padding: String =
+        std::iter::repeat_n(fill, (width.max(0) as usize).saturating_sub(len)).collect();
+    Ok(Value::String(Rc::new(if left {
+        padding + s
+    } else {
+        s.to_string() + &padding
+    })))
+}
+
+fn std_pad_left(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    pad(env, arg0, argc, true)
+}
+
+fn std_pad_right(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    pad(env, arg0, argc, false)
+}
+
+fn parse_int_radix(s: &str, radix: Option<u32>) -> Option<i64> {
+    let (sign, digits) = match s.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    let prefixed = [
+        ("0x", 16),
+        ("0X", 16),
+        ("0o", 8),
+        ("0O", 8),
+        ("0b", 2),
+        ("0B", 2),
+    ]
+    .into_iter()
+    .find_map(|(prefix, base)| digits.strip_prefix(prefix).map(|rest| (base, rest)));
+
+    let (radix, digits) = match (prefixed, radix) {
+        (Some((base, _)), Some(radix)) if base != radix => return None,
+        (Some((base, rest)), _) => (base, rest),
+        (None, radix) => (radix.unwrap_or(10), digits),
+    };
+
+    if !(2..=36).contains(&radix)
+        || digits.starts_with('_')
+        || digits.ends_with('_')
+        || digits.contains("__")
+    {
+        return None;
+    }
+
+    i64::from_str_radix(&format!("{}{}", sign, digits.replace('_', "")), radix).ok()
+}
+
+fn std_parse_int(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_range(env, argc, 1, 2)?;
+    let radix = match argc {
+        1 => None,
+        _ => match env.reg(arg0 + 1) {
+            Value::Int(r) => Some(u32::try_from(*r).unwrap_or(0)),
+            v => return error::Error::type_error(&Value::Int(0), v).err(),
+        },
+    };
+
+    match env.reg(arg0) {
+        Value::String(s) => match parse_int_radix(s.trim(), radix) {
+            Some(i) => Ok(Value::Int(i)),
+            None => error::Error::invalid_string_parse_input(s).err(),
         },
         v => error::Error::type_error(&Value::String(Rc::default()), v).err(),
     }
@@ -155,7 +1060,7 @@ fn std_parse_int(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error
 fn std_parse_float(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
     assert_arg_count(env, argc, 1)?;
     match env.reg(arg0) {
-        Value::String(s) => match s.parse().into() {
+        Value::String(s) => match s.trim().parse() {
             Ok(f) => Ok(Value::Float(f)),
             Err(_) => error::Error::invalid_string_parse_input(s).err(),
         },
@@ -163,24 +1068,184 @@ fn std_parse_float(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, err
     }
 }
 
+fn assertion(passed: bool, msg: impl FnOnce() -> String) -> Result<Value, error::Error> {
+    if passed {
+        Ok(Value::Null)
+    } else {
+        error::Error::custom_error(&format!("Assertion failed: {}", msg())).err()
+    }
+}
+
+fn test_assert_eq(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 2)?;
+    let (a, b) = (env.reg(arg0), env.reg(arg0 + 1));
+    assertion(a.deep_eq(b, env), || {
+        format!("expected {} to equal {}", a.repr(env), b.repr(env))
+    })
+}
+
+fn test_assert_neq(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 2)?;
+    let (a, b) = (env.reg(arg0), env.reg(arg0 + 1));
+    assertion(!a.deep_eq(b, env), || {
+        format!("expected {} to not equal {}", a.repr(env), b.repr(env))
+    })
+}
+
+fn test_assert_true(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    let v = env.reg(arg0).clone();
+    let truthy = env.truthy(&v)?;
+    assertion(truthy, || format!("expected {} to be true", v.repr(env)))
+}
+
+fn test_assert_false(env: &mut Env, arg0: usize, argc: usize) -> Result<Value, error::Error> {
+    assert_arg_count(env, argc, 1)?;
+    let v = env.reg(arg0).clone();
+    let truthy = env.truthy(&v)?;
+    assertion(!truthy, || format!("expected {} to be false", v.repr(env)))
+}
+
 pub fn register_standard_library(env: &mut Env) {
+    let wrapper = env.new_seg(Segment::native(
+        "<memoized>".to_string(),
+        1,
+        std_memoized_call,
+    ));
+    let memoize = env.new_seg(Segment::native("memoize".to_string(), 1, std_memoize));
+
     env.register_module(
         "std".to_string(),
         vec![
             ModuleFnRecord::new("println".to_string(), 1, std_println),
             ModuleFnRecord::new("print".to_string(), 1, std_print),
+            ModuleFnRecord::new("eprintln".to_string(), 1, std_eprintln),
+            ModuleFnRecord::new("eprint".to_string(), 1, std_eprint),
             ModuleFnRecord::new("typeOf".to_string(), 1, std_typeof),
             ModuleFnRecord::new("len".to_string(), 1, std_len),
+            ModuleFnRecord::new("isEmpty".to_string(), 1, std_is_empty),
+            ModuleFnRecord::new("arity".to_string(), 1, std_arity),
             ModuleFnRecord::new("str".to_string(), 1, std_str),
+            ModuleFnRecord::new("repr".to_string(), 1, std_repr),
+            ModuleFnRecord::new("summary".to_string(), 2, std_summary),
+            ModuleFnRecord::new("hash".to_string(), 1, std_hash),
+            ModuleFnRecord::new("format".to_string(), 1, std_format),
             ModuleFnRecord::new("append".to_string(), 2, std_array_append),
             ModuleFnRecord::new("insert".to_string(), 3, std_insert),
             ModuleFnRecord::new("remove".to_string(), 2, std_remove),
+            ModuleFnRecord::new("take".to_string(), 2, std_take),
             ModuleFnRecord::new("pop".to_string(), 1, std_array_pop),
+            ModuleFnRecord::new("sort".to_string(), 1, std_sort),
+            ModuleFnRecord::new("reverse".to_string(), 1, std_reverse),
             ModuleFnRecord::new("keys".to_string(), 1, std_object_keys),
+            ModuleFnRecord::new("merge".to_string(), 2, std_merge),
+            ModuleFnRecord::new("deepMerge".to_string(), 2, std_deep_merge),
+            ModuleFnRecord::new("flatten".to_string(), 1, std_flatten),
+            ModuleFnRecord::new("map".to_string(), 2, std_map),
+            ModuleFnRecord::new("flatMap".to_string(), 2, std_flat_map),
+            ModuleFnRecord::new("filter".to_string(), 2, std_filter),
+            ModuleFnRecord::new("zip".to_string(), 2, std_zip),
             ModuleFnRecord::new("gc".to_string(), 0, Env::gc),
             ModuleFnRecord::new("time".to_string(), 0, std_time),
-            ModuleFnRecord::new("parseInt".to_string(), 1, std_parse_int),
+            ModuleFnRecord::new("stackDepth".to_string(), 0, std_stack_depth),
+            ModuleFnRecord::new("error".to_string(), 1, std_error),
+            ModuleFnRecord::new("toUpper".to_string(), 1, std_to_upper),
+            ModuleFnRecord::new("toLower".to_string(), 1, std_to_lower),
+            ModuleFnRecord::new("capitalize".to_string(), 1, std_capitalize),
+            ModuleFnRecord::new("title".to_string(), 1, std_title),
+            ModuleFnRecord::new("split".to_string(), 2, std_split),
+            ModuleFnRecord::new("splitWhitespace".to_string(), 1, std_split_whitespace),
+            ModuleFnRecord::new("lines".to_string(), 1, std_lines),
+            ModuleFnRecord::new("charCodeAt".to_string(), 2, std_char_code_at),
+            ModuleFnRecord::new("codePoints".to_string(), 1, std_code_points),
+            ModuleFnRecord::new("repeat".to_string(), 2, std_repeat),
+            ModuleFnRecord::new("padLeft".to_string(), 3, std_pad_left),
+            ModuleFnRecord::new("padRight".to_string(), 3, std_pad_right),
+            ModuleFnRecord::new("floorMod".to_string(), 2, std_floor_mod),
+            ModuleFnRecord::new("abs".to_string(), 1, std_abs),
+            ModuleFnRecord::new("sign".to_string(), 1, std_sign),
+            ModuleFnRecord::new("clamp".to_string(), 3, std_clamp),
+            ModuleFnRecord::new("round".to_string(), 2, std_round),
+            ModuleFnRecord::new("roundHalfEven".to_string(), 2, std_round_half_even),
+            ModuleFnRecord::new("min".to_string(), 1, std_min),
+            ModuleFnRecord::new("max".to_string(), 1, std_max),
+            ModuleFnRecord::new("parseInt".to_string(), 2, std_parse_int),
             ModuleFnRecord::new("parseFloat".to_string(), 1, std_parse_float),
+            ModuleFnRecord::new("toFixed".to_string(), 2, std_to_fixed),
+            ModuleFnRecord::new("toHex".to_string(), 1, std_to_hex),
+            ModuleFnRecord::new("toBin".to_string(), 1, std_to_bin),
+        ],
+    );
+
+    // `memoize` captures its wrapper's segment, allocated after the module since
+    // a closure pointer of 0 stands for no closure
+    let memoize = env.new_native_closure(memoize, vec![Value::Func(wrapper as u32, 0)]);
+    if let Some(ptr) = env.module("std") {
+        if let HeapNode::Object { mark: _, map } = env.heap.access_mut(ptr) {
+            map.insert(Value::from_string("memoize"), memoize);
+        }
+    }
+
+    env.register_methods(
+        "Array",
+        vec![
+            ModuleFnRecord::new("len".to_string(), 1, std_len),
+            ModuleFnRecord::new("isEmpty".to_string(), 1, std_is_empty),
+            ModuleFnRecord::new("append".to_string(), 2, std_array_append),
+            ModuleFnRecord::new("insert".to_string(), 3, std_insert),
+            ModuleFnRecord::new("remove".to_string(), 2, std_remove),
+            ModuleFnRecord::new("pop".to_string(), 1, std_array_pop),
+            ModuleFnRecord::new("sort".to_string(), 1, std_sort),
+            ModuleFnRecord::new("reverse".to_string(), 1, std_reverse),
+            ModuleFnRecord::new("map".to_string(), 2, std_map),
+            ModuleFnRecord::new("flatMap".to_string(), 2, std_flat_map),
+            ModuleFnRecord::new("filter".to_string(), 2, std_filter),
+            ModuleFnRecord::new("zip".to_string(), 2, std_zip),
+            ModuleFnRecord::new("flatten".to_string(), 1, std_flatten),
+            ModuleFnRecord::new("min".to_string(), 1, std_min),
+            ModuleFnRecord::new("max".to_string(), 1, std_max),
+        ],
+    );
+
+    env.register_methods(
+        "String",
+        vec![
+            ModuleFnRecord::new("len".to_string(), 1, std_len),
+            ModuleFnRecord::new("isEmpty".to_string(), 1, std_is_empty),
+            ModuleFnRecord::new("upper".to_string(), 1, std_to_upper),
+            ModuleFnRecord::new("lower".to_string(), 1, std_to_lower),
+            ModuleFnRecord::new("capitalize".to_string(), 1, std_capitalize),
+            ModuleFnRecord::new("title".to_string(), 1, std_title),
+            ModuleFnRecord::new("split".to_string(), 2, std_split),
+            ModuleFnRecord::new("splitWhitespace".to_string(), 1, std_split_whitespace),
+            ModuleFnRecord::new("lines".to_string(), 1, std_lines),
+            ModuleFnRecord::new("charCodeAt".to_string(), 2, std_char_code_at),
+            ModuleFnRecord::new("codePoints".to_string(), 1, std_code_points),
+            ModuleFnRecord::new("repeat".to_string(), 2, std_repeat),
+            ModuleFnRecord::new("padLeft".to_string(), 3, std_pad_left),
+            ModuleFnRecord::new("padRight".to_string(), 3, std_pad_right),
+        ],
+    );
+
+    env.register_methods(
+        "Object",
+        vec![
+            ModuleFnRecord::new("len".to_string(), 1, std_len),
+            ModuleFnRecord::new("isEmpty".to_string(), 1, std_is_empty),
+            ModuleFnRecord::new("keys".to_string(), 1, std_object_keys),
+            ModuleFnRecord::new("merge".to_string(), 2, std_merge),
+            ModuleFnRecord::new("deepMerge".to_string(), 2, std_deep_merge),
+            ModuleFnRecord::new("take".to_string(), 2, std_take),
+        ],
+    );
+
+    env.register_module(
+        "test".to_string(),
+        vec![
+            ModuleFnRecord::new("assertEq".to_string(), 2, test_assert_eq),
+            ModuleFnRecord::new("assertNeq".to_string(), 2, test_assert_neq),
+            ModuleFnRecord::new("assertTrue".to_string(), 1, test_assert_true),
+            ModuleFnRecord::new("assertFalse".to_string(), 1, test_assert_false),
         ],
-    )
+    );
 }
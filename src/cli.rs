@@ -0,0 +1,80 @@
+use clap::Parser;
+use clap::Subcommand;
+
+use crate::{error, Interpreter, Value};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "The NewScript interpreter.", long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Enable debug output
+    #[arg(short = 'd', long = "debug", global = true)]
+    debug: bool,
+
+    /// Enable verbose output
+    #[arg(short = 'v', long = "verbose", global = true)]
+    verbose: bool,
+
+    // Command line arguments available in script
+    #[arg(short = 'a', long = "args", global = true, value_delimiter = ' ', num_args = 1..)]
+    arguments: Option<Vec<String>>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a file
+    Run {
+        /// Path to file to execute
+        file: String,
+    },
+
+    /// Start a REPL session
+    Repl,
+
+    /// Evaluate an expression
+    Eval {
+        // Expression to evaluate
+        expr: String,
+    },
+}
+
+/// Runs the command line, returning the process exit status
+pub fn run_cli(args: Cli) -> i32 {
+    let mut interpreter =
+        Interpreter::new(args.verbose, args.debug, args.arguments.unwrap_or(vec![]));
+
+    match args.command {
+        Command::Run { file } => match interpreter.execute_from_file(&file) {
+            // an integer returned from the top level becomes the exit code, which
+            // the OS truncates to a byte, so anything outside that range fails
+            Ok(Value::Int(code)) => match u8::try_from(code) {
+                Ok(code) => code as i32,
+                Err(_) => {
+                    error::Error::invalid_exit_code(code).dump_error(interpreter.environment());
+                    1
+                }
+            },
+            Ok(_) => 0,
+            Err(e) => {
+                e.dump_error(interpreter.environment());
+                1
+            }
+        },
+        Command::Eval { expr } => match interpreter.evaluate_from_string(&expr) {
+            Ok(v) => {
+                println!("{}", v.repr(interpreter.environment()));
+                0
+            }
+            Err(e) => {
+                e.dump_error(interpreter.environment());
+                1
+            }
+        },
+        Command::Repl => {
+            interpreter.repl();
+            0
+        }
+    }
+}
@@ -15,8 +15,10 @@ pub enum ErrorType {
     ArithmeticError(Value),
     ArgumentError(u32, u32),
     IndexError(u32),
+    KeyError(Value),
     ValueError,
     CustomError,
+    InternalError,
 }
 
 #[derive(Debug)]
@@ -24,9 +26,30 @@ pub struct Error {
     pub msg: String,
     pub err_type: ErrorType,
     pub pos: Option<io::Pos>,
+    pub span_end: Option<io::Pos>,
+    /// Set when parsing failed because input ended early, so more input may fix it
+    pub incomplete: bool,
 }
 
 impl ErrorType {
+    /// Stable numeric identifier of the error kind, independent of its display name
+    pub fn code(&self) -> u32 {
+        match self {
+            ErrorType::IOError => 1,
+            ErrorType::NameError(_) => 2,
+            ErrorType::SyntaxError => 3,
+            ErrorType::CompilerError => 4,
+            ErrorType::TypeError(_) => 5,
+            ErrorType::ArithmeticError(_) => 6,
+            ErrorType::ArgumentError(_, _) => 7,
+            ErrorType::IndexError(_) => 8,
+            ErrorType::KeyError(_) => 9,
+            ErrorType::ValueError => 10,
+            ErrorType::CustomError => 11,
+            ErrorType::InternalError => 12,
+        }
+    }
+
     fn to_string(&self) -> &'static str {
         match self {
             ErrorType::IOError => "IO ERROR",
@@ -37,8 +60,10 @@ impl ErrorType {
             ErrorType::ArithmeticError(_) => "ARITHMETIC ERROR",
             ErrorType::ArgumentError(_, _) => "ARGUMENT ERROR",
             ErrorType::IndexError(_) => "INDEX ERROR",
+            ErrorType::KeyError(_) => "KEY ERROR",
             ErrorType::ValueError => "VALUE ERROR",
             ErrorType::CustomError => "ERROR",
+            ErrorType::InternalError => "INTERNAL ERROR",
         }
     }
 }
@@ -48,11 +73,28 @@ impl Error {
         Err(self)
     }
 
+    pub fn code(&self) -> u32 {
+        self.err_type.code()
+    }
+
+    /// Sets the position of the error, keeping its span end unless the new
+    /// position is in a different source
     pub fn with_pos(self, pos: Option<&io::Pos>) -> Self {
         Self {
             err_type: self.err_type,
             msg: self.msg,
             pos: pos.cloned(),
+            span_end: self
+                .span_end
+                .filter(|end| pos.is_some_and(|pos| pos.src_id == end.src_id)),
+            incomplete: self.incomplete,
+        }
+    }
+
+    pub fn with_span_end(self, end: io::Pos) -> Self {
+        Self {
+            span_end: Some(end),
+            ..self
         }
     }
 
@@ -61,6 +103,8 @@ impl Error {
             msg: format!("Invalid token reached starting with {}", c),
             err_type: ErrorType::SyntaxError,
             pos: Some(pos),
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -69,6 +113,8 @@ impl Error {
             msg: format!("Invalid escape character in string: '\\{}'", c),
             err_type: ErrorType::SyntaxError,
             pos: Some(pos),
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -80,6 +126,8 @@ impl Error {
             ),
             err_type: ErrorType::SyntaxError,
             pos: Some(pos),
+            span_end: None,
+            incomplete: *tk0 == lexer::Tk::EOF,
         }
     }
 
@@ -88,14 +136,18 @@ impl Error {
             msg: format!("Unexpected token reached: '{:?}'", tk0),
             err_type: ErrorType::SyntaxError,
             pos: Some(pos),
+            span_end: None,
+            incomplete: *tk0 == lexer::Tk::EOF,
         }
     }
 
-    pub fn id_expected(pos: io::Pos) -> Self {
+    pub fn id_expected(tk0: &lexer::Tk, pos: io::Pos) -> Self {
         Self {
-            msg: format!("Unexpected token, identifier or symbol expected"),
+            msg: "Unexpected token, identifier or symbol expected".to_string(),
             err_type: ErrorType::SyntaxError,
             pos: Some(pos),
+            span_end: None,
+            incomplete: *tk0 == lexer::Tk::EOF,
         }
     }
 
@@ -104,6 +156,18 @@ impl Error {
             msg: format!("Cannot read file: '{}'", file_path),
             err_type: ErrorType::IOError,
             pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn write_error(reason: &str) -> Self {
+        Self {
+            msg: format!("Cannot write output: {}", reason),
+            err_type: ErrorType::IOError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -112,6 +176,91 @@ impl Error {
             msg: format!("Cannot parse string: '{}'", s),
             err_type: ErrorType::ValueError,
             pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn invalid_precision(n: i64) -> Self {
+        Self {
+            msg: format!("Invalid number of decimal places: {}", n),
+            err_type: ErrorType::ValueError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn invalid_limit(n: i64) -> Self {
+        Self {
+            msg: format!("Invalid element limit: {}", n),
+            err_type: ErrorType::ValueError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn invalid_exit_code(code: i64) -> Self {
+        Self {
+            msg: format!("Exit code must be between 0 and 255: {}", code),
+            err_type: ErrorType::ValueError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn invalid_fill(fill: &str) -> Self {
+        Self {
+            msg: format!("Fill must be a single character: '{}'", fill),
+            err_type: ErrorType::ValueError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn unpack_type(t0: &Value) -> Self {
+        Self {
+            msg: format!("Cannot destructure {}, expected an Array", t0.type_name()),
+            err_type: ErrorType::ValueError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn unpack_length(rec: usize, exp: usize) -> Self {
+        Self {
+            msg: format!(
+                "Cannot destructure Array of length {} into {} variables",
+                rec, exp
+            ),
+            err_type: ErrorType::ValueError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn invalid_bounds(lo: &str, hi: &str) -> Self {
+        Self {
+            msg: format!("Lower bound {} is greater than upper bound {}", lo, hi),
+            err_type: ErrorType::ValueError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn invalid_placeholder(placeholder: &str) -> Self {
+        Self {
+            msg: format!("Unmatched format placeholder: '{{{}}}'", placeholder),
+            err_type: ErrorType::ValueError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -123,6 +272,8 @@ impl Error {
             ),
             err_type: ErrorType::SyntaxError,
             pos: Some(pos),
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -134,30 +285,78 @@ impl Error {
             ),
             err_type: ErrorType::SyntaxError,
             pos: Some(pos),
+            span_end: None,
+            incomplete: false,
         }
     }
 
     pub fn invalid_ast_node(pos: io::Pos) -> Self {
         Self {
-            msg: format!("Unexpected AST node at this position - cannot be compiled"),
+            msg: "Unexpected AST node at this position - cannot be compiled".to_string(),
             err_type: ErrorType::CompilerError,
             pos: Some(pos),
+            span_end: None,
+            incomplete: false,
         }
     }
 
-    pub fn invalid_return_position(pos: io::Pos) -> Self {
+    pub fn invalid_register_range(r0: usize, r1: usize) -> Self {
         Self {
-            msg: format!("Return statement from invalid position"),
-            err_type: ErrorType::SyntaxError,
+            msg: format!("Invalid register range in bytecode: {}..{}", r0, r1),
+            err_type: ErrorType::CompilerError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn invalid_jump_target(pc: usize, target: usize) -> Self {
+        Self {
+            msg: format!("Invalid jump target in bytecode at {}: {}", pc, target),
+            err_type: ErrorType::CompilerError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn invalid_operand(pc: usize, operand: usize) -> Self {
+        Self {
+            msg: format!("Invalid operand in bytecode at {}: {}", pc, operand),
+            err_type: ErrorType::CompilerError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn infinite_loop(pos: io::Pos) -> Self {
+        Self {
+            msg: "Loop condition is always true and body has no break or return".to_string(),
+            err_type: ErrorType::CompilerError,
             pos: Some(pos),
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn invalid_step(pos: io::Pos) -> Self {
+        Self {
+            msg: "Range step must be a non-zero integer literal".to_string(),
+            err_type: ErrorType::CompilerError,
+            pos: Some(pos),
+            span_end: None,
+            incomplete: false,
         }
     }
 
     pub fn invalid_continue_pos(pos: io::Pos) -> Self {
         Self {
-            msg: format!("Continue statement outside of loop"),
+            msg: "Continue statement outside of loop".to_string(),
             err_type: ErrorType::SyntaxError,
             pos: Some(pos),
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -166,6 +365,8 @@ impl Error {
             msg: format!("Unknown variable referenced: '{}'", name),
             err_type: ErrorType::NameError(name),
             pos: Some(pos),
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -174,6 +375,8 @@ impl Error {
             msg: format!("Symbol name has already been used in scope: '{}'", name),
             err_type: ErrorType::NameError(name),
             pos: Some(pos),
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -185,6 +388,8 @@ impl Error {
             ),
             err_type: ErrorType::NameError(name),
             pos: Some(pos),
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -193,14 +398,28 @@ impl Error {
             msg: format!("Module not found: '{}'", name),
             err_type: ErrorType::NameError(name),
             pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn cyclic_import(name: String) -> Self {
+        Self {
+            msg: format!("Module imports itself while loading: '{}'", name),
+            err_type: ErrorType::NameError(name),
+            pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
     pub fn unexpected_null() -> Self {
         Self {
-            msg: format!("Recieved unexpected 'null' value"),
+            msg: "Recieved unexpected 'null' value".to_string(),
             err_type: ErrorType::TypeError("Null"),
             pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -213,6 +432,21 @@ impl Error {
             ),
             err_type: ErrorType::TypeError(t1.type_name()),
             pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn heap_type_error(expected: &str, received: &'static str) -> Self {
+        Self {
+            msg: format!(
+                "Unexpected heap value: Expected {} Recieved {}",
+                expected, received
+            ),
+            err_type: ErrorType::TypeError(received),
+            pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -221,6 +455,28 @@ impl Error {
             msg: format!("Unexpected type recieved: Recieved {}", t0.type_name()),
             err_type: ErrorType::TypeError(t0.type_name()),
             pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn no_length(t0: &Value) -> Self {
+        Self {
+            msg: format!("Value of type {} has no length", t0.type_name()),
+            err_type: ErrorType::TypeError(t0.type_name()),
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn not_iterable(t0: &Value) -> Self {
+        Self {
+            msg: format!("Type {} cannot be iterated over", t0.type_name()),
+            err_type: ErrorType::TypeError(t0.type_name()),
+            pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -232,6 +488,8 @@ impl Error {
             ),
             err_type: ErrorType::TypeError(t0.type_name()),
             pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -240,6 +498,8 @@ impl Error {
             msg: format!("Cannot apply operation '{}' to type {}", op, t0.type_name(),),
             err_type: ErrorType::TypeError(t0.type_name()),
             pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -253,22 +513,38 @@ impl Error {
             ),
             err_type: ErrorType::TypeError(t1.type_name()),
             pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
     pub fn negative_shift(v: i64) -> Self {
         Self {
-            msg: format!("Cannot apply bitwise shift operation using a signed integer",),
+            msg: "Cannot apply bitwise shift operation using a signed integer".to_string(),
+            err_type: ErrorType::ArithmeticError(Value::Int(v)),
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn integer_overflow(v: i64) -> Self {
+        Self {
+            msg: format!("Integer overflow on {}", v),
             err_type: ErrorType::ArithmeticError(Value::Int(v)),
             pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
     pub fn zero_division() -> Self {
         Self {
-            msg: format!("Zero division error"),
+            msg: "Zero division error".to_string(),
             err_type: ErrorType::ArithmeticError(Value::Int(0)),
             pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -277,6 +553,8 @@ impl Error {
             msg: format!("Cannot call non-function value of type {}", t0.type_name()),
             err_type: ErrorType::TypeError(t0.type_name()),
             pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -288,6 +566,8 @@ impl Error {
             ),
             err_type: ErrorType::ArgumentError(rec, exp),
             pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -296,6 +576,8 @@ impl Error {
             msg: format!("Invalid array length: {}", len,),
             err_type: ErrorType::IndexError(len),
             pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -304,6 +586,48 @@ impl Error {
             msg: format!("Invalid index: {}", idx,),
             err_type: ErrorType::IndexError(idx),
             pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn unknown_method(t0: &Value, name: &str) -> Self {
+        Self {
+            msg: format!("Type {} has no method '{}'", t0.type_name(), name),
+            err_type: ErrorType::TypeError(t0.type_name()),
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn duplicate_key(key_repr: &str, pos: io::Pos) -> Self {
+        Self {
+            msg: format!("Key appears more than once in object literal: {}", key_repr),
+            err_type: ErrorType::CompilerError,
+            pos: Some(pos),
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn missing_key(key: Value, key_repr: &str) -> Self {
+        Self {
+            msg: format!("Key not found: {}", key_repr),
+            err_type: ErrorType::KeyError(key),
+            pos: None,
+            span_end: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn invalid_heap_pointer(ptr: usize) -> Self {
+        Self {
+            msg: format!("Heap pointer does not reference a live object: {}", ptr),
+            err_type: ErrorType::InternalError,
+            pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -312,6 +636,8 @@ impl Error {
             msg: msg.to_string(),
             err_type: ErrorType::CustomError,
             pos: None,
+            span_end: None,
+            incomplete: false,
         }
     }
 
@@ -350,6 +676,6 @@ impl Error {
             )
         }
 
-        eprintln!("");
+        eprintln!();
     }
 }
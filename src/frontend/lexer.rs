@@ -5,7 +5,8 @@ use crate::utils::io;
 
 use super::operator::Op;
 
-#[derive(Debug, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Tk {
     Null,
     Int(i64),
@@ -19,7 +20,10 @@ pub enum Tk {
     Fun,
     If,
     Else,
+    Elif,
     While,
+    For,
+    In,
     Return,
     Break,
     Continue,
@@ -36,15 +40,19 @@ pub enum Tk {
     Semi,
     Comma,
     Dot,
+    Ellipsis,
+    DotDot,
     Colon,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub tk: Tk,
     pub pos: io::Pos,
+    pub end: io::Pos,
 }
 
+#[derive(Clone)]
 pub struct Lexer<'a> {
     stream: Chars<'a>,
     current_char: char,
@@ -56,12 +64,32 @@ pub struct Lexer<'a> {
 
 impl Token {
     pub fn new(tk: Tk, pos: io::Pos) -> Self {
-        Self { tk, pos }
+        Self { tk, pos, end: pos }
     }
 
     pub fn as_id(&self) -> Option<&String> {
         match &self.tk {
-            Tk::Id(id) => Some(&id),
+            Tk::Id(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Source text of a reserved word, for positions where keywords are
+    /// accepted as plain names such as object keys
+    pub fn as_keyword(&self) -> Option<&'static str> {
+        match &self.tk {
+            Tk::Let => Some("let"),
+            Tk::Fun => Some("fun"),
+            Tk::If => Some("if"),
+            Tk::Else => Some("else"),
+            Tk::Elif => Some("elif"),
+            Tk::While => Some("while"),
+            Tk::For => Some("for"),
+            Tk::In => Some("in"),
+            Tk::Return => Some("return"),
+            Tk::Break => Some("break"),
+            Tk::Continue => Some("continue"),
+            Tk::Import => Some("import"),
             _ => None,
         }
     }
@@ -104,6 +132,16 @@ impl<'a> Lexer<'a> {
         &self.tks[(self.tki) % 3]
     }
 
+    /// Token after the head, already lexed
+    pub fn lookahead_token(&self) -> &Token {
+        &self.tks[(self.tki + 2) % 3]
+    }
+
+    /// Lexes the token after the lookahead without advancing this lexer
+    pub fn peek_token(&self) -> Result<Token, error::Error> {
+        self.clone().next_valid_token().cloned()
+    }
+
     fn advance(&mut self) -> char {
         if self.current_char == '\n' {
             self.cursor.column = -1;
@@ -125,7 +163,7 @@ impl<'a> Lexer<'a> {
 
         let tk = match c {
             c if c.is_ascii_alphabetic() || c == '_' => self.extract_identifier(),
-            c if c.is_digit(10) => self.extract_number(),
+            c if c.is_ascii_digit() => self.extract_number(),
             '"' => self.extract_string()?,
             '#' => self.extract_comment(),
             '{' => Tk::LeftBrace,
@@ -137,6 +175,15 @@ impl<'a> Lexer<'a> {
             ';' => Tk::Semi,
             ':' => Tk::Colon,
             ',' => Tk::Comma,
+            '.' if self.lookahead_char == '.' => {
+                self.advance();
+                if self.lookahead_char != '.' {
+                    Tk::DotDot
+                } else {
+                    self.advance();
+                    Tk::Ellipsis
+                }
+            }
             '.' => Tk::Dot,
             '\n' => Tk::Newline,
             '\0' => Tk::EOF,
@@ -185,7 +232,12 @@ impl<'a> Lexer<'a> {
                 }
                 ('>', '>') => {
                     self.advance();
-                    Tk::Operator(Op::Shr)
+                    if self.lookahead_char == '>' {
+                        self.advance();
+                        Tk::Operator(Op::Ushr)
+                    } else {
+                        Tk::Operator(Op::Shr)
+                    }
                 }
                 ('<', '<') => {
                     self.advance();
@@ -216,7 +268,11 @@ impl<'a> Lexer<'a> {
             },
         };
 
-        Ok(Token::new(tk, pos))
+        Ok(Token {
+            tk,
+            pos,
+            end: self.cursor,
+        })
     }
 
     fn extract_identifier(&mut self) -> Tk {
@@ -231,7 +287,10 @@ impl<'a> Lexer<'a> {
             "fun" => Tk::Fun,
             "if" => Tk::If,
             "else" => Tk::Else,
+            "elif" => Tk::Elif,
             "while" => Tk::While,
+            "for" => Tk::For,
+            "in" => Tk::In,
             "return" => Tk::Return,
             "true" => Tk::Bool(true),
             "false" => Tk::Bool(false),
@@ -247,7 +306,10 @@ impl<'a> Lexer<'a> {
         let mut buf = self.current_char.to_string();
         let mut is_float = false;
 
-        while self.lookahead_char.is_digit(10) || (self.lookahead_char == '.' && !is_float) {
+        // a second dot means a range such as `0..n` rather than a decimal point
+        while self.lookahead_char.is_ascii_digit()
+            || (self.lookahead_char == '.' && !is_float && self.stream.clone().next() != Some('.'))
+        {
             is_float = is_float || self.lookahead_char == '.';
             buf.push(self.advance());
         }
@@ -269,6 +331,10 @@ impl<'a> Lexer<'a> {
                     'n' => '\n',
                     'r' => '\r',
                     't' => '\t',
+                    '0' => '\0',
+                    'b' => '\x08',
+                    'f' => '\x0c',
+                    'x' => self.extract_hex_escape()?,
                     '"' => '"',
                     '\\' => '\\',
                     c => return error::Error::invalid_escape_char(c, self.cursor).err(),
@@ -282,6 +348,23 @@ impl<'a> Lexer<'a> {
         Ok(Tk::String(buf))
     }
 
+    fn extract_hex_escape(&mut self) -> Result<char, error::Error> {
+        let pos = self.cursor;
+        let mut byte = 0;
+
+        for _ in 0..2 {
+            match self.lookahead_char.to_digit(16) {
+                Some(d) => {
+                    self.advance();
+                    byte = byte * 16 + d;
+                }
+                None => return error::Error::invalid_escape_char('x', pos).err(),
+            }
+        }
+
+        Ok(char::from(byte as u8))
+    }
+
     fn extract_comment(&mut self) -> Tk {
         while self.lookahead_char != '\n' && self.lookahead_char != '\0' {
             self.advance();
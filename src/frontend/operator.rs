@@ -19,6 +19,7 @@ pub enum Op {
     And,
     Not,
     Shr,
+    Ushr,
     Shl,
     Assign,
     AddEq,
@@ -33,8 +34,13 @@ pub enum Op {
 }
 
 impl Op {
+    pub fn is_assign(&self) -> bool {
+        self.precedence() == 0
+    }
+
     pub fn precedence(&self) -> u8 {
         match self {
+            Op::Assign | Op::AddEq | Op::SubEq | Op::MulEq | Op::DivEq | Op::ModEq => 0,
             Op::Or => 1,
             Op::And => 2,
             Op::BitOr => 3,
@@ -42,11 +48,10 @@ impl Op {
             Op::BitAnd => 5,
             Op::Eq | Op::Neq => 6,
             Op::Gt | Op::Ge | Op::Lt | Op::Le => 7,
-            Op::Shl | Op::Shr => 8,
+            Op::Shl | Op::Shr | Op::Ushr => 8,
             Op::Add | Op::Sub => 9,
             Op::Mul | Op::Div | Op::Mod => 10,
-            Op::Not | Op::BitNot => 11,
-            _ => MAX_BIN_OP_PRECEDENCE,
+            Op::Not | Op::BitNot => MAX_BIN_OP_PRECEDENCE,
         }
     }
 
@@ -67,6 +72,7 @@ impl Op {
             Op::And => "&&",
             Op::Not => "!",
             Op::Shr => ">>",
+            Op::Ushr => ">>>",
             Op::Shl => "<<",
             Op::Assign => "=",
             Op::AddEq => "+=",
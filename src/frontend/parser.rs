@@ -21,19 +21,25 @@ pub enum Ast {
     String(String),
     Object(Vec<(AstNode, AstNode)>),
     Array(Vec<AstNode>),
+    Spread(Box<AstNode>),
     Reference(String),
     Block(Vec<AstNode>),
     TernaryExp(Box<AstNode>, Box<AstNode>, Box<AstNode>),
     BinaryExp(Op, Box<AstNode>, Box<AstNode>),
     UnaryExp(Op, Box<AstNode>),
     Subscript(Box<AstNode>, Box<AstNode>),
+    Slice(Box<AstNode>, Option<Box<AstNode>>, Option<Box<AstNode>>),
     Call(Box<AstNode>, Vec<AstNode>),
     Deref(Box<AstNode>, String),
     Let(String, Box<AstNode>),
+    LetObject(Vec<String>, Box<AstNode>),
+    LetArray(Vec<String>, Box<AstNode>),
     Assign(Op, Box<AstNode>, Box<AstNode>),
     Return(Option<Box<AstNode>>),
     If(Box<AstNode>, Box<AstNode>, Option<Box<AstNode>>),
     While(Box<AstNode>, Box<AstNode>),
+    For(Vec<String>, Box<AstNode>, Box<AstNode>),
+    Range(Box<AstNode>, Box<AstNode>, Option<Box<AstNode>>),
     FuncDef(Option<String>, Vec<String>, Box<AstNode>),
     Break,
     Continue,
@@ -113,11 +119,24 @@ impl AstNode {
                 writeln!(f, "{} {:?}", "unary-expression".green(), op)?;
                 a.print_tree(f, stem, level + 1, true)
             }
+            Ast::Spread(a) => {
+                writeln!(f, "{}", "spread".green())?;
+                a.print_tree(f, stem, level + 1, true)
+            }
             Ast::Subscript(a, b) => {
                 writeln!(f, "{}", "subscript".green())?;
                 a.print_tree(f, stem, level + 1, false)?;
                 b.print_tree(f, stem, level + 1, true)
             }
+            Ast::Slice(a, b, c) => {
+                writeln!(f, "{}", "slice".green())?;
+                let bounds: Vec<&AstNode> = b.iter().chain(c.iter()).map(|e| &**e).collect();
+                a.print_tree(f, stem, level + 1, bounds.is_empty())?;
+                for (i, e) in bounds.iter().enumerate() {
+                    e.print_tree(f, stem, level + 1, i + 1 == bounds.len())?;
+                }
+                Ok(())
+            }
             Ast::Deref(a, b) => {
                 writeln!(f, "{} ->{}", "attribute-dereference".green(), b)?;
                 a.print_tree(f, stem, level + 1, true)
@@ -126,6 +145,14 @@ impl AstNode {
                 writeln!(f, "{} {}", "var-declaration".green(), a)?;
                 b.print_tree(f, stem, level + 1, true)
             }
+            Ast::LetObject(a, b) => {
+                writeln!(f, "{} {{{}}}", "var-declaration".green(), a.join(", "))?;
+                b.print_tree(f, stem, level + 1, true)
+            }
+            Ast::LetArray(a, b) => {
+                writeln!(f, "{} ({})", "var-declaration".green(), a.join(", "))?;
+                b.print_tree(f, stem, level + 1, true)
+            }
             Ast::Assign(op, a, b) => {
                 writeln!(f, "{} {:?}", "var-assignment".green(), op)?;
                 a.print_tree(f, stem, level + 1, false)?;
@@ -155,6 +182,21 @@ impl AstNode {
                 a.print_tree(f, stem, level + 1, false)?;
                 b.print_tree(f, stem, level + 1, true)
             }
+            Ast::Range(a, b, c) => {
+                writeln!(f, "{}", "range".green())?;
+                a.print_tree(f, stem, level + 1, false)?;
+                if let Some(c) = c {
+                    b.print_tree(f, stem, level + 1, false)?;
+                    c.print_tree(f, stem, level + 1, true)
+                } else {
+                    b.print_tree(f, stem, level + 1, true)
+                }
+            }
+            Ast::For(ids, a, b) => {
+                writeln!(f, "{} ({})", "for-loop".green(), ids.join(", "))?;
+                a.print_tree(f, stem, level + 1, false)?;
+                b.print_tree(f, stem, level + 1, true)
+            }
             Ast::FuncDef(a, args, b) => {
                 let v = a.clone().unwrap_or("<lambda>".to_string());
                 writeln!(f, "{} {}({})", "function".green(), v, args.join(", "))?;
@@ -162,7 +204,7 @@ impl AstNode {
             }
             Ast::Call(a, v) => {
                 writeln!(f, "{}", "function-call".green())?;
-                a.print_tree(f, stem, level + 1, v.len() == 0)?;
+                a.print_tree(f, stem, level + 1, v.is_empty())?;
 
                 for (i, node) in v.iter().enumerate() {
                     node.print_tree(f, stem, level + 1, i == v.len() - 1)?
@@ -189,7 +231,9 @@ impl AstNode {
             Ast::Object(vec) => {
                 writeln!(f, "{}", "object-literal".green())?;
                 for (i, (k, v)) in vec.iter().enumerate() {
-                    if i == vec.len() - 1 {
+                    if matches!(k.ast, Ast::Spread(_)) {
+                        k.print_tree(f, stem, level + 1, i == vec.len() - 1)?;
+                    } else if i == vec.len() - 1 {
                         k.print_tree(f, stem, level + 1, true)?;
                         v.print_tree(f, stem, level + 2, true)?
                     } else {
@@ -241,7 +285,9 @@ impl<'a> Parser<'a> {
         if head.tk == tk {
             self.consume()
         } else {
-            error::Error::unexpected_token(&head.tk, &tk, head.pos).err()
+            error::Error::unexpected_token(&head.tk, &tk, head.pos)
+                .with_span_end(head.end)
+                .err()
         }
     }
 
@@ -251,7 +297,9 @@ impl<'a> Parser<'a> {
         if let Tk::Id(id) = &head.tk {
             Ok(id)
         } else {
-            error::Error::id_expected(head.pos).err()
+            error::Error::id_expected(&head.tk, head.pos)
+                .with_span_end(head.end)
+                .err()
         }
     }
 
@@ -261,6 +309,22 @@ impl<'a> Parser<'a> {
         self.parse_block()
     }
 
+    /// Parses a source holding a single expression as an assignment of its value
+    /// to `target`, so evaluations report positions within the original source
+    pub fn parse_assignment_to(&mut self, target: &str) -> Result<AstNode, error::Error> {
+        self.lexer.next_valid_token()?;
+        self.lexer.next_valid_token()?;
+
+        let e = self.parse_expression()?;
+        self.consume_if(Tk::Semi)?;
+        self.expect(Tk::EOF)?;
+
+        let pos = e.pos;
+        let target = AstNode::new(Ast::Reference(target.to_string()), pos);
+        let assign = AstNode::new(Ast::Assign(Op::Assign, Box::new(target), Box::new(e)), pos);
+        Ok(AstNode::new(Ast::Block(vec![assign]), pos))
+    }
+
     fn parse_block(&mut self) -> Result<AstNode, error::Error> {
         let mut statements: Vec<AstNode> = Vec::new();
 
@@ -287,11 +351,15 @@ impl<'a> Parser<'a> {
         match &self.head().tk {
             Tk::If => self.parse_if_stmt(),
             Tk::While => self.parse_loop(),
+            Tk::For => self.parse_for_loop(),
             Tk::Let => self.parse_let(),
             Tk::Return => self.parse_return(),
+            // an unnamed function at statement position is an expression, e.g. invoked at once
+            Tk::Fun if self.lexer.lookahead_token().tk == Tk::LeftParen => {
+                self.parse_assign_or_expression()
+            }
             Tk::Fun => self.parse_function(false),
-            Tk::Id(_) => self.parse_assign_or_call(),
-            Tk::Import => self.parse_assign_or_call(),
+            Tk::LeftBrace if !self.is_object_statement()? => self.parse_scoped_block(),
             Tk::Break => {
                 let pos = self.consume()?.pos;
                 self.expect(Tk::Semi)?;
@@ -302,17 +370,63 @@ impl<'a> Parser<'a> {
                 self.expect(Tk::Semi)?;
                 Ok(AstNode::new(Ast::Continue, pos))
             }
-            tk => error::Error::unexpected_token_any(tk, self.head().pos).err(),
+            _ => self.parse_assign_or_expression(),
         }
     }
 
+    /// A `{` at statement position opens an object literal only when followed
+    /// by a spread or a single-token key and a colon, otherwise it opens a block
+    fn is_object_statement(&self) -> Result<bool, error::Error> {
+        Ok(match self.lexer.lookahead_token().tk {
+            Tk::Ellipsis => true,
+            Tk::RightBrace | Tk::EOF => false,
+            _ => self.lexer.peek_token()?.tk == Tk::Colon,
+        })
+    }
+
     fn parse_let(&mut self) -> Result<AstNode, error::Error> {
         let pos = self.expect(Tk::Let)?.pos;
-        let id = self
-            .consume()?
+
+        if self.consume_if(Tk::LeftBrace)? {
+            let mut ids = vec![self.expect_id()?.to_string()];
+            while self.consume_if(Tk::Comma)? {
+                ids.push(self.expect_id()?.to_string());
+            }
+            self.expect(Tk::RightBrace)?;
+
+            self.expect(Tk::Operator(Op::Assign))?;
+            let e = Box::new(self.parse_expression()?);
+            self.expect(Tk::Semi)?;
+
+            return Ok(AstNode::new(Ast::LetObject(ids, e), pos));
+        }
+
+        if self.consume_if(Tk::LeftParen)? {
+            let mut ids = vec![self.expect_id()?.to_string()];
+            while self.consume_if(Tk::Comma)? {
+                ids.push(self.expect_id()?.to_string());
+            }
+            self.expect(Tk::RightParen)?;
+
+            self.expect(Tk::Operator(Op::Assign))?;
+            let e = Box::new(self.parse_expression()?);
+            self.expect(Tk::Semi)?;
+
+            return Ok(AstNode::new(Ast::LetArray(ids, e), pos));
+        }
+
+        let head = self.consume()?;
+        let id = head
             .as_id()
             .map(|s| s.to_string())
-            .ok_or(error::Error::id_expected(pos))?;
+            .ok_or(error::Error::id_expected(&head.tk, pos))?;
+
+        if self.consume_if(Tk::Semi)? {
+            return Ok(AstNode::new(
+                Ast::Let(id, Box::new(AstNode::new(Ast::Null, pos))),
+                pos,
+            ));
+        }
 
         self.expect(Tk::Operator(Op::Assign))?;
         let e = Box::new(self.parse_expression()?);
@@ -334,32 +448,45 @@ impl<'a> Parser<'a> {
         Ok(AstNode::new(Ast::Import(path), pos))
     }
 
-    fn parse_assign_or_call(&mut self) -> Result<AstNode, error::Error> {
+    fn parse_assign_or_expression(&mut self) -> Result<AstNode, error::Error> {
         let pos = self.head().pos;
-        let id = self.parse_reference()?;
-
-        let op = match &self.consume()?.tk {
-            Tk::Operator(
-                op @ (Op::Assign | Op::AddEq | Op::SubEq | Op::MulEq | Op::ModEq | Op::DivEq),
-            ) => Ok(*op),
-            Tk::Operator(op) => error::Error::non_assign_op(*op, self.head().pos).err(),
-            Tk::Semi if matches!(id.ast, Ast::Call(_, _)) => return Ok(id),
-            tk => error::Error::unexpected_token_any(tk, pos).err(),
-        }?;
+        let e = self.parse_expression()?;
 
-        let e = Box::new(self.parse_expression()?);
+        // assignment is only a statement, so it is not parsed as an operator
+        let op = match self.head().tk {
+            Tk::Operator(op) if op.is_assign() => op,
+            _ => return self.expect(Tk::Semi).map(|_| e),
+        };
+
+        if !matches!(
+            e.ast,
+            Ast::Reference(_) | Ast::Subscript(_, _) | Ast::Deref(_, _)
+        ) {
+            return error::Error::unexpected_token_any(&self.head().tk, self.head().pos)
+                .with_span_end(self.head().end)
+                .err();
+        }
+
+        self.consume()?;
+        let rhs = self.parse_expression()?;
         self.expect(Tk::Semi)?;
 
-        Ok(AstNode::new(Ast::Assign(op, Box::new(id), e), pos))
+        Ok(AstNode::new(
+            Ast::Assign(op, Box::new(e), Box::new(rhs)),
+            pos,
+        ))
     }
 
     fn parse_if_stmt(&mut self) -> Result<AstNode, error::Error> {
         let pos = self.expect(Tk::If)?.pos;
+        self.parse_if_branches(pos)
+    }
 
+    fn parse_if_branches(&mut self, pos: io::Pos) -> Result<AstNode, error::Error> {
         let cond = Box::new(self.parse_expression()?);
         let block1 = Box::new(self.parse_scoped_block()?);
         let block2 = match self.head().tk {
-            Tk::Else => Some(Box::new(self.parse_else_stmts()?)),
+            Tk::Else | Tk::Elif => Some(Box::new(self.parse_else_stmts()?)),
             _ => None,
         };
 
@@ -367,29 +494,84 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_else_stmts(&mut self) -> Result<AstNode, error::Error> {
+        // `elif` is shorthand for `else if`
+        if self.head().tk == Tk::Elif {
+            let pos = self.consume()?.pos;
+            return self.parse_if_branches(pos);
+        }
+
         self.expect(Tk::Else)?;
 
         match &self.head().tk {
             Tk::If => self.parse_if_stmt(),
             Tk::LeftBrace => self.parse_scoped_block(),
-            tk => error::Error::unexpected_token_any(tk, self.head().pos).err(),
+            tk => error::Error::unexpected_token_any(tk, self.head().pos)
+                .with_span_end(self.head().end)
+                .err(),
         }
     }
 
     fn parse_loop(&mut self) -> Result<AstNode, error::Error> {
         let pos = self.expect(Tk::While)?.pos;
-        let cond = Box::new(self.parse_expression()?);
+        let cond = if matches!(self.head().tk, Tk::Let) {
+            let pos = self.consume()?.pos;
+            let id = self.expect_id()?.to_string();
+            self.expect(Tk::Operator(Op::Assign))?;
+            let e = self.parse_expression()?;
+            Box::new(AstNode::new(Ast::Let(id, Box::new(e)), pos))
+        } else {
+            Box::new(self.parse_expression()?)
+        };
         let block = Box::new(self.parse_scoped_block()?);
         Ok(AstNode::new(Ast::While(cond, block), pos))
     }
 
+    fn parse_for_loop(&mut self) -> Result<AstNode, error::Error> {
+        let pos = self.expect(Tk::For)?.pos;
+
+        let ids = if self.consume_if(Tk::LeftParen)? {
+            let mut ids = vec![self.expect_id()?.to_string()];
+            while self.consume_if(Tk::Comma)? {
+                ids.push(self.expect_id()?.to_string());
+            }
+            self.expect(Tk::RightParen)?;
+            ids
+        } else {
+            vec![self.expect_id()?.to_string()]
+        };
+
+        self.expect(Tk::In)?;
+        let mut e = Box::new(self.parse_expression()?);
+        if self.consume_if(Tk::DotDot)? {
+            let end = Box::new(self.parse_expression()?);
+
+            // `step` is only a keyword after a range, so it remains a valid name
+            let step = if matches!(&self.head().tk, Tk::Id(id) if id == "step") {
+                self.consume()?;
+                Some(Box::new(self.parse_expression()?))
+            } else {
+                None
+            };
+            e = Box::new(AstNode::new(Ast::Range(e, end, step), pos));
+        }
+        let block = Box::new(self.parse_scoped_block()?);
+        Ok(AstNode::new(Ast::For(ids, e, block), pos))
+    }
+
     fn parse_return(&mut self) -> Result<AstNode, error::Error> {
         let pos = self.expect(Tk::Return)?.pos;
 
         let e = if self.consume_if(Tk::Semi)? {
             None
         } else {
-            let e1 = self.parse_expression()?;
+            let mut e1 = self.parse_expression()?;
+            if matches!(self.head().tk, Tk::Comma) {
+                let mut values = vec![e1];
+                while self.consume_if(Tk::Comma)? {
+                    values.push(self.parse_expression()?);
+                }
+                e1 = AstNode::new(Ast::Array(values), pos);
+            }
             self.expect(Tk::Semi)?;
             Some(Box::new(e1))
         };
@@ -484,7 +666,9 @@ impl<'a> Parser<'a> {
                 self.expect(Tk::RightParen)?;
                 Ok(node)
             }
-            tk => error::Error::unexpected_token_any(tk, self.head().pos).err(),
+            tk => error::Error::unexpected_token_any(tk, self.head().pos)
+                .with_span_end(self.head().end)
+                .err(),
         }
     }
 
@@ -502,19 +686,34 @@ impl<'a> Parser<'a> {
                 }
                 Tk::LeftBracket => {
                     self.consume()?;
-                    lhs = AstNode::new(
-                        Ast::Subscript(Box::new(lhs), Box::new(self.parse_expression()?)),
-                        pos,
-                    );
+                    let start = match self.head().tk {
+                        Tk::Colon => None,
+                        _ => Some(Box::new(self.parse_expression()?)),
+                    };
+
+                    lhs = match (start, self.head().tk == Tk::Colon) {
+                        (Some(start), false) => {
+                            AstNode::new(Ast::Subscript(Box::new(lhs), start), pos)
+                        }
+                        (start, _) => {
+                            self.expect(Tk::Colon)?;
+                            let end = match self.head().tk {
+                                Tk::RightBracket => None,
+                                _ => Some(Box::new(self.parse_expression()?)),
+                            };
+                            AstNode::new(Ast::Slice(Box::new(lhs), start, end), pos)
+                        }
+                    };
                     self.expect(Tk::RightBracket)?;
                 }
                 Tk::Dot => {
                     self.consume()?;
-                    let attr = self
-                        .consume()?
+                    let head = self.consume()?;
+                    let attr = head
                         .as_id()
                         .map(|s| s.to_string())
-                        .ok_or(error::Error::id_expected(pos))?;
+                        .or(head.as_keyword().map(|s| s.to_string()))
+                        .ok_or(error::Error::id_expected(&head.tk, pos))?;
                     lhs = AstNode::new(Ast::Deref(Box::new(lhs), attr), pos)
                 }
                 _ => unreachable!(),
@@ -563,15 +762,25 @@ impl<'a> Parser<'a> {
         Ok(AstNode::new(Ast::FuncDef(id, args, block), pos))
     }
 
+    fn parse_spread_or_expression(&mut self) -> Result<AstNode, error::Error> {
+        if matches!(self.head().tk, Tk::Ellipsis) {
+            let pos = self.consume()?.pos;
+            let e = self.parse_expression()?;
+            Ok(AstNode::new(Ast::Spread(Box::new(e)), pos))
+        } else {
+            self.parse_expression()
+        }
+    }
+
     fn parse_array(&mut self) -> Result<AstNode, error::Error> {
         let pos = self.expect(Tk::LeftBracket)?.pos;
         let mut values = Vec::<AstNode>::new();
 
         if !matches!(self.head().tk, Tk::RightBracket) {
-            values.push(self.parse_expression()?);
+            values.push(self.parse_spread_or_expression()?);
 
             while self.consume_if(Tk::Comma)? {
-                values.push(self.parse_expression()?);
+                values.push(self.parse_spread_or_expression()?);
             }
         }
 
@@ -584,18 +793,35 @@ impl<'a> Parser<'a> {
         let mut values = Vec::<(AstNode, AstNode)>::new();
 
         if !matches!(self.head().tk, Tk::RightBrace) {
-            let key = self.parse_expression()?;
-            self.expect(Tk::Colon)?;
-            values.push((key, self.parse_expression()?));
+            values.push(self.parse_object_entry()?);
 
             while self.consume_if(Tk::Comma)? {
-                let key = self.parse_expression()?;
-                self.expect(Tk::Colon)?;
-                values.push((key, self.parse_expression()?));
+                values.push(self.parse_object_entry()?);
             }
         }
 
         self.expect(Tk::RightBrace)
             .map(|_| AstNode::new(Ast::Object(values), pos))
     }
+
+    fn parse_object_entry(&mut self) -> Result<(AstNode, AstNode), error::Error> {
+        // a reserved word directly followed by `:` is taken as a string key
+        let keyword = match self.head().as_keyword() {
+            Some(k) if self.lexer.lookahead_token().tk == Tk::Colon => Some(k),
+            _ => None,
+        };
+
+        let key = match keyword {
+            Some(k) => AstNode::new(Ast::String(k.to_string()), self.consume()?.pos),
+            None => self.parse_spread_or_expression()?,
+        };
+
+        if matches!(key.ast, Ast::Spread(_)) {
+            let pos = key.pos;
+            return Ok((key, AstNode::new(Ast::Null, pos)));
+        }
+
+        self.expect(Tk::Colon)?;
+        Ok((key, self.parse_expression()?))
+    }
 }
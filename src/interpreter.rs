@@ -8,7 +8,11 @@ use colored::Colorize;
 use crate::{
     backend::compiler::Compiler,
     error,
-    frontend::{lexer::Lexer, parser::Parser},
+    frontend::{
+        lexer::Lexer,
+        parser::{AstNode, Parser},
+    },
+    utils::io::{Pos, Source},
     vm::{self, Env},
 };
 
@@ -17,12 +21,112 @@ pub struct Interpreter {
     verbose: bool,
     debug: bool,
     debug_segment_count: usize,
+    last_value: Option<vm::Value>,
+}
+
+/// Declaratively configures an [`Interpreter`] with host modules and globals
+#[derive(Default)]
+pub struct InterpreterBuilder {
+    verbose: bool,
+    debug: bool,
+    args: Vec<String>,
+    heap_capacity: Option<usize>,
+    modules: Vec<(String, Vec<vm::ModuleExport>)>,
+    globals: Vec<(String, vm::Value)>,
+    stdout: Option<Box<dyn Write>>,
+    stderr: Option<Box<dyn Write>>,
+}
+
+impl InterpreterBuilder {
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    pub fn args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    pub fn heap_capacity(mut self, heap_capacity: usize) -> Self {
+        self.heap_capacity = Some(heap_capacity);
+        self
+    }
+
+    /// Adds a module importable by scripts, registered in the order given
+    pub fn module(mut self, name: &str, exports: Vec<impl Into<vm::ModuleExport>>) -> Self {
+        self.modules.push((
+            name.to_string(),
+            exports.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    pub fn global(mut self, name: &str, value: vm::Value) -> Self {
+        self.globals.push((name.to_string(), value));
+        self
+    }
+
+    /// Captures script output instead of writing it to the process's stdout
+    pub fn stdout(mut self, writer: Box<dyn Write>) -> Self {
+        self.stdout = Some(writer);
+        self
+    }
+
+    /// Captures script diagnostics instead of writing them to the process's stderr
+    pub fn stderr(mut self, writer: Box<dyn Write>) -> Self {
+        self.stderr = Some(writer);
+        self
+    }
+
+    pub fn build(self) -> Interpreter {
+        let env = match self.heap_capacity {
+            Some(capacity) => Env::new_with_heap(capacity, self.args),
+            None => Env::new(self.args),
+        };
+
+        let mut interpreter = Interpreter::from_env(env, self.verbose, self.debug);
+        for (name, exports) in self.modules {
+            interpreter.env.register_module(name, exports);
+        }
+        for (name, value) in self.globals {
+            interpreter.env.set_global(name, value);
+        }
+        if let Some(writer) = self.stdout {
+            interpreter.env.set_stdout(writer);
+        }
+        if let Some(writer) = self.stderr {
+            interpreter.env.set_stderr(writer);
+        }
+
+        interpreter
+    }
 }
 
 impl Interpreter {
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
+
     pub fn new(verbose: bool, debug: bool, args: Vec<String>) -> Self {
-        let mut env = Env::new(args);
+        Self::from_env(Env::new(args), verbose, debug)
+    }
 
+    pub fn with_heap_capacity(
+        verbose: bool,
+        debug: bool,
+        args: Vec<String>,
+        heap_capacity: usize,
+    ) -> Self {
+        Self::from_env(Env::new_with_heap(heap_capacity, args), verbose, debug)
+    }
+
+    fn from_env(mut env: Env, verbose: bool, debug: bool) -> Self {
         env.get_segment_mut(0)
             .symbols_mut()
             .insert("_".to_string(), 0);
@@ -34,6 +138,7 @@ impl Interpreter {
             verbose,
             debug,
             debug_segment_count,
+            last_value: None,
         }
     }
 
@@ -45,18 +150,35 @@ impl Interpreter {
         &mut self.env
     }
 
-    fn run(&mut self, source_id: u32) -> Result<(), error::Error> {
+    /// Result of the most recent successful evaluation
+    pub fn last_value(&self) -> Option<&vm::Value> {
+        self.last_value.as_ref()
+    }
+
+    /// Parses a source as statements, or as a single expression assigned to `_`
+    fn parse(src: &Source, evaluate: bool) -> Result<AstNode, error::Error> {
+        let mut lexer = Lexer::new(src);
+        let mut parser = Parser::new(&mut lexer);
+
+        if evaluate {
+            parser.parse_assignment_to("_")
+        } else {
+            parser.parse()
+        }
+    }
+
+    fn run(&mut self, source_id: u32, evaluate: bool) -> Result<vm::Value, error::Error> {
+        self.env.clear_trace();
         let src = self.env.sources.get_source(source_id).unwrap();
 
         if !self.debug && !self.verbose {
-            Ok(src)
-                .and_then(|src| Parser::new(&mut Lexer::new(src)).parse())
+            Self::parse(src, evaluate)
                 .and_then(|ast| Compiler::new(&mut self.env).compile(&ast).map(drop))
                 .and_then(|_| self.env.execute(0, 0))
         } else {
             let mut start = Instant::now();
 
-            let ast = Parser::new(&mut Lexer::new(src)).parse()?;
+            let ast = Self::parse(src, evaluate)?;
             if self.verbose {
                 println!(
                     "[{}] Parsing took: {} microseconds",
@@ -103,36 +225,72 @@ impl Interpreter {
         }
     }
 
-    pub fn execute_from_file(&mut self, file_path: &str) -> Result<(), error::Error> {
+    /// Compiles source without running it and returns the name and declaration
+    /// position of every named function it defines, for use by editor tooling
+    pub fn outline(source: &str) -> Result<Vec<(String, Pos)>, error::Error> {
+        let mut env = Env::new(vec![]);
+        let first = env.segments().len();
+
+        let src_id = env.sources.load_source_string(source)?.id();
+        let src = env.sources.get_source(src_id).unwrap();
+        let ast = Parser::new(&mut Lexer::new(src)).parse()?;
+        Compiler::new(&mut env).compile(&ast)?;
+
+        Ok(env.segments()[first..]
+            .iter()
+            .filter(|seg| seg.native_function_pointer().is_none() && seg.name() != "<lambda>")
+            .filter_map(|seg| seg.pos().map(|pos| (seg.name().to_string(), *pos)))
+            .collect())
+    }
+
+    /// Runs a script file, returning the value of a top-level `return` or null
+    pub fn execute_from_file(&mut self, file_path: &str) -> Result<vm::Value, error::Error> {
         self.env.get_segment_mut(0).clear_definition();
         self.env
             .sources
             .load_source_file(file_path)
             .map(|src| src.id())
-            .and_then(|src_id| self.run(src_id))
+            .and_then(|src_id| self.run(src_id, false))
     }
 
-    pub fn execute_from_string(&mut self, source: &str) -> Result<(), error::Error> {
+    /// Runs a script, returning the value of a top-level `return` or null
+    pub fn execute_from_string(&mut self, source: &str) -> Result<vm::Value, error::Error> {
         self.env.get_segment_mut(0).clear_definition();
         self.env
             .sources
             .load_source_string(source)
             .map(|src| src.id())
-            .and_then(|src_id| self.run(src_id))
+            .and_then(|src_id| self.run(src_id, false))
     }
 
     pub fn evaluate_from_string(&mut self, source: &str) -> Result<vm::Value, error::Error> {
         self.env.get_segment_mut(0).clear_definition();
         self.env
             .sources
-            .load_source_string(&format!("_ = {};", source))
+            .load_source_string(source)
             .map(|src| src.id())
-            .and_then(|src_id| self.run(src_id))
-            .map(|_| self.env.reg(0).clone())
+            .and_then(|src_id| self.run(src_id, true))
+            .map(|_| {
+                let v = self
+                    .env
+                    .get_global(&"_".to_string())
+                    .cloned()
+                    .unwrap_or(vm::Value::Null);
+                self.last_value = Some(v.clone());
+                v
+            })
     }
 
     pub fn repl(&mut self) {
-        println!(
+        self.repl_with(io::stdin().lock(), io::stdout());
+    }
+
+    /// Runs a REPL session reading commands from `input` and writing prompts and
+    /// results to `output`, until `exit` or the end of input. Errors are still
+    /// reported on stderr.
+    pub fn repl_with(&mut self, mut input: impl io::BufRead, mut output: impl Write) {
+        let _ = writeln!(
+            output,
             "Welcome to the NewScript REPL. To execute statements, type command, terminate \
              with ';' and hit enter. To evaluate expressions, prefix commands with '=' (no \
              semicolon needed). Type 'exit' to kill REPL."
@@ -140,28 +298,31 @@ impl Interpreter {
 
         let _ = self.execute_from_string("let std = import(\"std\");");
 
-        let mut input = String::new();
+        let mut line = String::new();
         loop {
-            print!(">> ");
-            io::stdout().flush().unwrap();
+            let _ = write!(output, ">> ");
+            let _ = output.flush();
 
-            input.clear();
+            line.clear();
 
-            if let Err(e) = io::stdin().read_line(&mut input) {
-                eprintln!("Failed to read from standard input: {}", e);
-                break;
+            match input.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Failed to read from standard input: {}", e);
+                    break;
+                }
             }
 
-            match input.trim() {
+            match line.trim() {
                 "exit" => {
-                    println!("Closing REPL session. Goodbye :)");
+                    let _ = writeln!(output, "Closing REPL session. Goodbye :)");
                     break;
                 }
                 e if e.starts_with('=') => match self.evaluate_from_string(&e[1..]) {
                     Err(e) => e.dump_error(&self.env),
-                    Ok(_) => {
-                        let v = self.env.reg(0);
-                        println!("{}", v.repr(&self.env))
+                    Ok(v) => {
+                        let _ = writeln!(output, "{}", v.repr(&self.env));
                     }
                 },
                 e => {
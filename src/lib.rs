@@ -1,14 +1,23 @@
 mod backend;
+mod cli;
 pub mod error;
 mod frontend;
 mod interpreter;
 mod utils;
 mod vm;
 
+pub use backend::opcodes::Ins;
+pub use backend::opcodes::Reg;
+pub use cli::run_cli;
+pub use cli::Cli;
 pub use interpreter::Interpreter;
+pub use interpreter::InterpreterBuilder;
+pub use utils::io::Pos;
 pub use vm::heap::Alloc;
 pub use vm::heap::HeapNode;
+pub use vm::heap::ObjectMap;
 pub use vm::Env;
+pub use vm::ModuleExport;
 pub use vm::ModuleFnRecord;
 pub use vm::NativeFnPtr;
 pub use vm::Value;
@@ -1,6 +1,12 @@
 pub mod io {
     use crate::error;
-    use std::{cmp, fs, str::Chars};
+    use std::{
+        cmp,
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+        str::Chars,
+    };
 
     type SourceId = u32;
 
@@ -9,6 +15,7 @@ pub mod io {
         id: SourceId,
         src_origin: String,
         src_content: String,
+        src_path: Option<PathBuf>,
     }
 
     #[derive(Clone, Copy, Debug, PartialEq)]
@@ -22,6 +29,8 @@ pub mod io {
 
     pub struct SourceManager {
         sources: Vec<Source>,
+        named: HashMap<String, SourceId>,
+        tab_width: usize,
     }
 
     impl Source {
@@ -29,18 +38,45 @@ pub mod io {
             self.id
         }
 
-        pub fn char_stream(&self) -> Chars {
+        pub fn char_stream(&self) -> Chars<'_> {
             self.src_content.chars()
         }
 
         pub fn get_origin(&self) -> &String {
             &self.src_origin
         }
+
+        /// Path of the file the source was read from, absolute when it exists
+        pub fn path(&self) -> Option<&Path> {
+            self.src_path.as_deref()
+        }
     }
 
     impl SourceManager {
         pub fn new() -> Self {
-            Self { sources: vec![] }
+            Self {
+                sources: vec![],
+                named: HashMap::new(),
+                tab_width: 4,
+            }
+        }
+
+        pub fn tab_width(&self) -> usize {
+            self.tab_width
+        }
+
+        /// Sets the number of spaces tabs expand to in sources loaded afterwards
+        pub fn set_tab_width(&mut self, tab_width: usize) {
+            self.tab_width = tab_width;
+        }
+
+        /// Expands tabs and converts line endings to `\n` so positions are
+        /// reported the same regardless of how a source was loaded
+        fn normalize(&self, content: &str) -> String {
+            content
+                .replace("\r\n", "\n")
+                .replace('\r', "\n")
+                .replace('\t', &" ".repeat(self.tab_width))
         }
 
         pub fn get_source(&self, id: u32) -> Option<&Source> {
@@ -52,7 +88,7 @@ pub mod io {
                 Ok(content) => {
                     self.sources.push(Source {
                         id: self.sources.len() as u32,
-                        src_content: content.replace("\t", "    "),
+                        src_content: self.normalize(&content),
                         src_origin: fs::canonicalize(file_path)
                             .map(|p| {
                                 p.into_os_string()
@@ -62,6 +98,9 @@ pub mod io {
                                     .to_string()
                             })
                             .unwrap_or(file_path.to_string()),
+                        src_path: Some(
+                            fs::canonicalize(file_path).unwrap_or(PathBuf::from(file_path)),
+                        ),
                     });
 
                     Ok(self.sources.last().unwrap())
@@ -73,13 +112,34 @@ pub mod io {
         pub fn load_source_string(&mut self, source_string: &str) -> Result<&Source, error::Error> {
             self.sources.push(Source {
                 id: self.sources.len() as u32,
-                src_content: source_string.replace("\t", "    "),
+                src_content: self.normalize(source_string),
                 src_origin: "<input>".to_string(),
+                src_path: None,
             });
 
             Ok(self.sources.last().unwrap())
         }
 
+        /// Registers an in-memory source under a name, which is reported as its
+        /// origin and can be imported by scripts. Adding a name again replaces
+        /// what later imports of it resolve to.
+        pub fn load_named_source(&mut self, name: &str, content: &str) -> &Source {
+            let id = self.sources.len() as u32;
+            self.sources.push(Source {
+                id,
+                src_content: self.normalize(content),
+                src_origin: name.to_string(),
+                src_path: None,
+            });
+            self.named.insert(name.to_string(), id);
+
+            self.sources.last().unwrap()
+        }
+
+        pub fn get_named_source(&self, name: &str) -> Option<&Source> {
+            self.named.get(name).and_then(|id| self.get_source(*id))
+        }
+
         pub fn get_line(&self, pos: &Pos) -> Option<String> {
             match self.get_source(pos.src_id) {
                 Some(src) => {
@@ -1,16 +1,25 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use crate::{
     backend::{
+        compiler::Compiler,
         opcodes::{Ins, Reg},
         stdlib,
     },
     error,
+    frontend::{lexer::Lexer, operator::Op, parser::Parser},
     utils::io,
 };
 
 use super::{
-    heap::{Alloc, Heap, HeapNode},
+    heap::{Alloc, Heap, HeapNode, ObjectMap},
     segment::Segment,
     value::Value,
     NativeFnPtr,
@@ -22,6 +31,7 @@ struct CallInfo {
     program: usize,
     closure: usize,
     retloc: usize,
+    argc: usize,
 }
 
 pub struct ModuleFnRecord {
@@ -30,6 +40,14 @@ pub struct ModuleFnRecord {
     arg_count: Reg,
 }
 
+/// Entry of a native module, either a function, a plain value or a nested
+/// namespace of further exports
+pub enum ModuleExport {
+    Function(ModuleFnRecord),
+    Value(String, Value),
+    Object(String, Vec<ModuleExport>),
+}
+
 pub struct Env {
     segments: Vec<Segment>,
     calls: Vec<CallInfo>,
@@ -38,17 +56,51 @@ pub struct Env {
     pub heap: Heap,
     pub sources: io::SourceManager,
     modules: HashMap<String, usize>,
+    module_names: HashMap<usize, String>,
+    file_modules: HashMap<String, Value>,
+    loading: HashSet<String>,
+    roots: Vec<Value>,
+    native_top: usize,
+    native_closure: usize,
+    trace: Vec<io::Pos>,
+    gc_enabled: bool,
+    append_on_assign: bool,
+    empty_falsy: bool,
+    methods: HashMap<&'static str, ObjectMap>,
+    profile: Option<HashMap<(usize, usize), u64>>,
+    displaying: HashSet<usize>,
+    stdout: Box<dyn Write>,
+    stderr: Box<dyn Write>,
 }
 
 impl Env {
     pub fn new(args: Vec<String>) -> Self {
+        Self::new_with_heap(8, args)
+    }
+
+    pub fn new_with_heap(heap_capacity: usize, args: Vec<String>) -> Self {
         let mut env = Self {
             calls: vec![],
             registers: vec![Value::Null; 1024],
             globals: vec![],
-            heap: Heap::new(8),
+            heap: Heap::new(heap_capacity),
             sources: io::SourceManager::new(),
             modules: HashMap::new(),
+            module_names: HashMap::new(),
+            file_modules: HashMap::new(),
+            loading: HashSet::new(),
+            roots: vec![],
+            native_top: 0,
+            native_closure: 0,
+            trace: vec![],
+            gc_enabled: true,
+            append_on_assign: false,
+            empty_falsy: true,
+            profile: None,
+            displaying: HashSet::new(),
+            stdout: Box::new(std::io::stdout()),
+            stderr: Box::new(std::io::stderr()),
+            methods: HashMap::new(),
             segments: vec![
                 Segment::empty("__start".to_string(), true),
                 Segment::native("__import".to_string(), 1, Self::import),
@@ -67,7 +119,16 @@ impl Env {
         env
     }
 
+    /// Call stack positions recorded when the last error was raised
     pub fn trace_pos(&self) -> Vec<io::Pos> {
+        self.trace.clone()
+    }
+
+    pub fn clear_trace(&mut self) {
+        self.trace.clear();
+    }
+
+    fn live_trace_pos(&self) -> Vec<io::Pos> {
         self.calls
             .iter()
             .rev()
@@ -82,11 +143,10 @@ impl Env {
         match args.first() {
             Some(Value::String(name)) => {
                 let module = name.to_string();
-                match self.modules.get(&module) {
-                    Some(v) => Ok(Value::Object(*v)),
-                    None => error::Error::module_not_found(module)
-                        .with_pos(self.last_call_pos())
-                        .err(),
+                match (self.modules.get(&module), self.file_modules.get(&module)) {
+                    (Some(v), _) => Ok(Value::Object(*v)),
+                    (None, Some(v)) => Ok(v.clone()),
+                    (None, None) => self.import_script(&module),
                 }
             }
             _ => error::Error::argument_error(0, 1)
@@ -95,38 +155,378 @@ impl Env {
         }
     }
 
-    pub fn register_module(&mut self, name: String, exports: Vec<ModuleFnRecord>) {
-        let mut module = HashMap::new();
+    /// Registers an in-memory script importable by name, consulted before the
+    /// filesystem, returning its source id
+    pub fn add_named_source(&mut self, name: &str, contents: &str) -> u32 {
+        self.sources.load_named_source(name, contents).id()
+    }
+
+    /// Runs a script module from a named source, or failing that a file, once
+    /// and caches the value it results in under its name, or for files its path
+    fn import_script(&mut self, module: &str) -> Result<Value, error::Error> {
+        let pos = self.last_call_pos().cloned();
+        let not_found =
+            || error::Error::module_not_found(module.to_string()).with_pos(pos.as_ref());
+
+        let (key, src) = match self.sources.get_named_source(module) {
+            Some(src) => (module.to_string(), src),
+            None => {
+                let path = self
+                    .resolve_import(module, pos.as_ref())
+                    .ok_or_else(not_found)?;
+                let key = path.to_string_lossy().to_string();
+                if let Some(v) = self.file_modules.get(&key) {
+                    return Ok(v.clone());
+                }
+
+                let src = self
+                    .sources
+                    .load_source_file(&key)
+                    .map_err(|_| not_found())?;
+                (key, src)
+            }
+        };
+
+        // a module still running its body has no value to hand out yet
+        if !self.loading.insert(key.clone()) {
+            return error::Error::cyclic_import(module.to_string())
+                .with_pos(pos.as_ref())
+                .err();
+        }
 
-        for method in exports {
-            module.insert(
-                Value::from_string(&method.name),
-                Value::Func(self.segments().len() as u32, 0),
-            );
+        let result = Parser::new(&mut Lexer::new(src))
+            .parse()
+            .and_then(|ast| Compiler::new(self).compile_module(module, &ast))
+            .and_then(|fid| self.call_function(&Value::Func(fid as u32, 0), &[]));
+        self.loading.remove(&key);
+
+        let v = result?;
+        self.file_modules.insert(key, v.clone());
+        Ok(v)
+    }
 
+    /// Finds the file imported by a relative or absolute path, where relative
+    /// paths are taken from the directory of the importing file, or otherwise
+    /// the working directory, and `.ns` may be left out
+    fn resolve_import(&self, module: &str, pos: Option<&io::Pos>) -> Option<PathBuf> {
+        let dir = pos
+            .and_then(|pos| self.sources.get_source(pos.src_id))
+            .and_then(|src| src.path())
+            .and_then(Path::parent);
+
+        let path = match dir {
+            Some(dir) => dir.join(module),
+            None => PathBuf::from(module),
+        };
+
+        let with_ext = path
+            .extension()
+            .is_none()
+            .then(|| path.with_extension("ns"));
+        [Some(path), with_ext]
+            .into_iter()
+            .flatten()
+            .find(|path| path.is_file())
+            .and_then(|path| fs::canonicalize(path).ok())
+    }
+
+    pub fn gc_enabled(&self) -> bool {
+        self.gc_enabled
+    }
+
+    /// Enables or disables automatic collection before heap allocations,
+    /// explicit calls to `gc` are unaffected
+    pub fn set_gc_enabled(&mut self, enabled: bool) {
+        self.gc_enabled = enabled;
+    }
+
+    pub fn append_on_assign(&self) -> bool {
+        self.append_on_assign
+    }
+
+    /// Allows `arr[len(arr)] = v` to append to an array rather than raising an
+    /// index error, assignment to any other out of range index still fails
+    pub fn set_append_on_assign(&mut self, enabled: bool) {
+        self.append_on_assign = enabled;
+    }
+
+    pub fn empty_collections_falsy(&self) -> bool {
+        self.empty_falsy
+    }
+
+    /// Sets whether empty arrays and objects are falsy in conditions, as empty
+    /// strings are, which is the default. Otherwise they are always truthy.
+    pub fn set_empty_collections_falsy(&mut self, enabled: bool) {
+        self.empty_falsy = enabled;
+    }
+
+    /// Truthiness of a value in conditions, calling the `__bool__` function of
+    /// an object that defines one with the object
+    pub fn truthy(&mut self, v: &Value) -> Result<bool, error::Error> {
+        match Self::truthiness(&self.heap, self.empty_falsy, v) {
+            Ok(truthy) => Ok(truthy),
+            Err(f) => {
+                let result = self.call_function(&f, std::slice::from_ref(v))?;
+                Ok(Self::truthiness(&self.heap, self.empty_falsy, &result).unwrap_or(true))
+            }
+        }
+    }
+
+    /// Truthiness of a value, or for an object with a `__bool__` function the
+    /// function, as calling it needs the whole environment
+    fn truthiness(heap: &Heap, empty_falsy: bool, v: &Value) -> Result<bool, Value> {
+        match v {
+            Value::Array(p) => match heap.access(*p) {
+                HeapNode::Array { mark: _, vec } => Ok(!empty_falsy || !vec.is_empty()),
+                _ => unreachable!("value-pointer heap-object type mismatch"),
+            },
+            Value::Object(p) => match heap.access(*p) {
+                HeapNode::Object { mark: _, map } => match map.get(&Value::from_string("__bool__"))
+                {
+                    Some(f @ Value::Func(..)) => Err(f.clone()),
+                    _ => Ok(!empty_falsy || !map.is_empty()),
+                },
+                _ => unreachable!("value-pointer heap-object type mismatch"),
+            },
+            v => Ok(v.truthy()),
+        }
+    }
+
+    /// Suspends the frame to call an object's `__bool__` function with it, then
+    /// resumes the frame once the truthiness is known
+    fn call_bool_hook(
+        &mut self,
+        ci: CallInfo,
+        f: Value,
+        v: Value,
+    ) -> Result<(CallInfo, bool), error::Error> {
+        self.calls.push(ci);
+        let result = self.call_function(&f, &[v]);
+        let ci = self.calls.pop().unwrap();
+
+        let result = result?;
+        let truthy = Self::truthiness(&self.heap, self.empty_falsy, &result).unwrap_or(true);
+        Ok((ci, truthy))
+    }
+
+    /// Writer receiving script output, such as from `println`
+    pub fn stdout(&mut self) -> &mut dyn Write {
+        self.stdout.as_mut()
+    }
+
+    /// Redirects script output, the process's standard output by default
+    pub fn set_stdout(&mut self, writer: Box<dyn Write>) {
+        self.stdout = writer;
+    }
+
+    /// Writer receiving script diagnostics, such as from `eprintln`
+    pub fn stderr(&mut self) -> &mut dyn Write {
+        self.stderr.as_mut()
+    }
+
+    /// Redirects script diagnostics, the process's standard error by default
+    pub fn set_stderr(&mut self, writer: Box<dyn Write>) {
+        self.stderr = writer;
+    }
+
+    pub fn profiling(&self) -> bool {
+        self.profile.is_some()
+    }
+
+    /// Enables counting how many times each instruction executes, enabling
+    /// resets the counts and disabling discards them
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profile = enabled.then(HashMap::new);
+    }
+
+    /// Execution counts per source line, each paired with the earliest position
+    /// recorded on the line and ordered by source then line. A line's count is
+    /// that of its most executed instruction.
+    pub fn profile_report(&self) -> Vec<(io::Pos, u64)> {
+        let mut lines: BTreeMap<(u32, u32), (io::Pos, u64)> = BTreeMap::new();
+
+        for (&(program, pc), &count) in self.profile.iter().flatten() {
+            let Some(pos) = self.segments[program].get_pos(pc) else {
+                continue;
+            };
+
+            let entry = lines.entry((pos.src_id, pos.line)).or_insert((*pos, 0));
+            if pos.offset < entry.0.offset {
+                entry.0 = *pos;
+            }
+            entry.1 = entry.1.max(count);
+        }
+
+        lines.into_values().collect()
+    }
+
+    /// Runs the garbage collector if automatic collection is enabled and the
+    /// heap has reached its collection threshold. Must only be called while
+    /// all live values are reachable from registers, globals or modules.
+    pub fn maybe_collect(&mut self) -> bool {
+        if self.gc_enabled && self.heap.should_collect() {
+            self.collect();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Suspends the frame on the call stack so its registers are rooted, collects,
+    /// then allocates the node into register `r` of the frame
+    fn collect_and_store(
+        &mut self,
+        mut ci: CallInfo,
+        r: Reg,
+        node: HeapNode,
+        wrap: impl FnOnce(usize) -> Value,
+    ) {
+        let dst = ci.sp + r as usize;
+        ci.pc += 1;
+        self.calls.push(ci);
+
+        self.maybe_collect();
+        self.registers[dst] = wrap(self.heap.allocate(node));
+    }
+
+    pub fn register_module(&mut self, name: String, exports: Vec<impl Into<ModuleExport>>) {
+        // nested objects are unreachable until the module is stored, so collect up front
+        self.maybe_collect();
+        let ptr = self.build_module(exports.into_iter().map(Into::into).collect());
+        if let Some(old) = self.modules.insert(name.clone(), ptr) {
+            self.module_names.remove(&old);
+        }
+        self.module_names.insert(ptr, name);
+    }
+
+    /// Registers native functions callable as methods on values of the named type,
+    /// `v.name(args...)` calls the function with `v` as its first argument. Object
+    /// members take precedence over methods registered for objects.
+    pub fn register_methods(&mut self, type_name: &'static str, methods: Vec<ModuleFnRecord>) {
+        for method in methods {
+            let f = Value::Func(self.segments().len() as u32, 0);
+            let name = Value::from_string(&method.name);
             self.segments_mut().push(Segment::native(
                 method.name,
                 method.arg_count,
                 method.function_pointer,
             ));
+            self.methods.entry(type_name).or_default().insert(name, f);
         }
+    }
 
-        let ptr = self.heap.allocate(HeapNode::object(module));
-        self.modules.insert(name, ptr);
+    /// Heap pointer of the module registered under the name, if any
+    pub fn module(&self, name: &str) -> Option<usize> {
+        self.modules.get(name).copied()
     }
 
+    /// Name of the registered module stored at the heap pointer, if any
+    pub fn module_name(&self, ptr: usize) -> Option<&String> {
+        self.module_names.get(&ptr)
+    }
+
+    fn build_module(&mut self, exports: Vec<ModuleExport>) -> usize {
+        let mut module = ObjectMap::default();
+
+        for export in exports {
+            let (name, value) = match export {
+                ModuleExport::Function(method) => {
+                    let f = Value::Func(self.segments().len() as u32, 0);
+                    self.segments_mut().push(Segment::native(
+                        method.name.clone(),
+                        method.arg_count,
+                        method.function_pointer,
+                    ));
+                    (method.name, f)
+                }
+                ModuleExport::Value(name, value) => (name, value),
+                ModuleExport::Object(name, exports) => {
+                    (name, Value::Object(self.build_module(exports)))
+                }
+            };
+
+            module.insert(Value::from_string(&name), value);
+        }
+
+        self.heap.allocate(HeapNode::object(module))
+    }
+
+    /// Keeps a value alive across collections while it is only held by native code,
+    /// must be balanced with a call to `pop_root`
+    pub fn push_root(&mut self, value: Value) {
+        self.roots.push(value);
+    }
+
+    pub fn pop_root(&mut self) -> Option<Value> {
+        self.roots.pop()
+    }
+
+    /// Collects garbage then releases the unused tail of the heap, returning the
+    /// number of heap nodes released
+    pub fn compact(&mut self) -> usize {
+        self.collect();
+        self.heap.compact()
+    }
+
+    /// Collects garbage then returns the number of heap values still reachable
+    pub fn heap_live_count(&mut self) -> usize {
+        self.collect();
+        self.heap.occupied()
+    }
+
+    /// Collects garbage then counts the reachable heap values of each kind, as
+    /// `Array`, `Object` or `Closure`, to help track down leaks in embeddings
+    pub fn heap_object_types(&mut self) -> HashMap<&'static str, usize> {
+        self.collect();
+        self.heap.type_counts()
+    }
+
+    /// Elements of the array at `ptr`, for hosts reading script results
+    /// without matching on `HeapNode`
+    pub fn iter_array(&self, ptr: usize) -> Result<impl Iterator<Item = &Value>, error::Error> {
+        match self.heap.try_access(ptr)? {
+            HeapNode::Array { mark: _, vec } => Ok(vec.iter()),
+            node => error::Error::heap_type_error("Array", node.type_name().unwrap()).err(),
+        }
+    }
+
+    /// Key-value pairs of the object at `ptr`, in the object's iteration order
+    pub fn iter_object(
+        &self,
+        ptr: usize,
+    ) -> Result<impl Iterator<Item = (&Value, &Value)>, error::Error> {
+        match self.heap.try_access(ptr)? {
+            HeapNode::Object { mark: _, map } => Ok(map.iter()),
+            node => error::Error::heap_type_error("Object", node.type_name().unwrap()).err(),
+        }
+    }
+
+    /// Collects garbage, with the signature of a native function so scripts can
+    /// call it as `std.gc`. Collection itself cannot fail.
     pub fn gc(&mut self, _arg0: usize, _argc: usize) -> Result<Value, error::Error> {
-        let active_register_range = 0..self
+        self.collect();
+        Ok(Value::Null)
+    }
+
+    /// Marks everything reachable from registers, globals, native roots and
+    /// modules, registered or imported, then sweeps the rest
+    fn collect(&mut self) {
+        // every suspended frame's window and any running native's arguments
+        // (reserved through `native_top`) lie below the highest window end
+        let frames_top = self
             .calls
-            .last()
-            .map(|call| call.sp + self.segments[call.program].slots() as usize)
+            .iter()
+            .map(|call| call.sp + self.segments[call.program].slots() as usize + 1)
+            .max()
             .unwrap_or(0);
-
-        let global_register_range = 0..self.get_segment(0).symbols().len();
+        let active_register_range = 0..frames_top.max(self.native_top).min(self.registers.len());
+        // globals are only sized for new symbols once the program next runs
+        let global_register_range = 0..self.get_segment(0).symbols().len().min(self.globals.len());
 
         for register in self.registers[active_register_range]
             .iter()
             .chain(self.globals[global_register_range].iter())
+            .chain(self.roots.iter())
         {
             if let Value::Object(p) | Value::Array(p) | Value::Func(_, p) = register {
                 self.heap.mark(*p)
@@ -137,8 +537,13 @@ impl Env {
             self.heap.mark(*module);
         }
 
+        for v in self.file_modules.values() {
+            if let Value::Object(p) | Value::Array(p) | Value::Func(_, p) = v {
+                self.heap.mark(*p)
+            }
+        }
+
         self.heap.sweep();
-        Ok(Value::Null)
     }
 
     pub fn new_seg(&mut self, segment: Segment) -> usize {
@@ -184,31 +589,288 @@ impl Env {
         self.globals[register] = value;
     }
 
+    /// Every global variable currently bound to a function, sorted by name, so
+    /// hosts such as test runners can discover and `call_function` them
+    pub fn global_functions(&self) -> Vec<(String, Value)> {
+        let mut functions: Vec<(String, Value)> = self
+            .get_segment(0)
+            .symbols()
+            .iter()
+            .filter(|(name, _)| !name.starts_with('<'))
+            .filter_map(|(name, r)| match self.globals.get(*r as usize) {
+                Some(f @ Value::Func(_, _)) => Some((name.clone(), f.clone())),
+                _ => None,
+            })
+            .collect();
+
+        functions.sort_by(|a, b| a.0.cmp(&b.0));
+        functions
+    }
+
+    /// Builds a function value calling the native segment `fid` with `values`
+    /// captured, which the native reads back through `captured` when called.
+    /// The values must be rooted until the function value is stored.
+    pub fn new_native_closure(&mut self, fid: usize, values: Vec<Value>) -> Value {
+        Value::Func(fid as u32, self.heap.allocate(HeapNode::closure(values)))
+    }
+
+    /// Values captured by the running native function, empty unless it was
+    /// called through a function value built by `new_native_closure`
+    pub fn captured(&self) -> &[Value] {
+        match self.native_closure {
+            0 => &[],
+            p => match self.heap.access(p) {
+                HeapNode::Closure { mark: _, vals } => vals,
+                _ => &[],
+            },
+        }
+    }
+
+    /// Number of frames on the call stack, a running native's own frame excluded
+    pub fn call_depth(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// Current value of every named variable in the frame at `frame`, counted
+    /// from the outermost, for debuggers and error dumps to show program state.
+    /// Returns `None` past the innermost frame.
+    pub fn locals_snapshot(&self, frame: usize) -> Option<HashMap<String, Value>> {
+        let call = self.calls.get(frame)?;
+        let seg = &self.segments[call.program];
+
+        Some(
+            seg.symbols()
+                .iter()
+                .filter(|(name, _)| !name.starts_with('<'))
+                .map(|(name, r)| {
+                    let value = if seg.is_global() {
+                        self.globals.get(*r as usize)
+                    } else {
+                        self.registers.get(call.sp + *r as usize)
+                    };
+                    (name.clone(), value.cloned().unwrap_or(Value::Null))
+                })
+                .collect(),
+        )
+    }
+
     pub fn last_call_pos(&self) -> Option<&io::Pos> {
         self.calls
             .last()
             .and_then(|call| self.segments[call.program].get_pos(call.pc))
     }
 
-    pub fn execute(&mut self, program: usize, closure: usize) -> Result<(), error::Error> {
-        self.globals
-            .resize(self.get_segment(program).symbols().len() * 2, Value::Null);
+    /// First register above the window of the innermost active frame and any
+    /// argument windows reserved by `call_function`
+    fn stack_top(&self) -> usize {
+        self.calls
+            .last()
+            .map(|call| call.sp + self.segments[call.program].slots() as usize + 1)
+            .unwrap_or(0)
+            .max(self.native_top)
+    }
+
+    /// Runs a segment to completion, returning the value of a top-level
+    /// `return`, or null when it runs off the end. Its registers are cleared
+    /// once it returns, so other results must be read from globals.
+    ///
+    /// Natives may call this too: the new frame starts above every active
+    /// frame, and the globals table only ever grows, so the caller's state is
+    /// left intact when running a function segment.
+    pub fn execute(&mut self, program: usize, closure: usize) -> Result<Value, error::Error> {
+        let segment = self.get_segment(program);
+        if segment.is_global() {
+            let len = segment.symbols().len() * 2;
+            if len > self.globals.len() {
+                self.globals.resize(len, Value::Null);
+            }
+        }
+
+        if self.calls.is_empty() {
+            self.trace.clear();
+        }
+
+        let depth = self.calls.len();
+        let sp = self.stack_top();
+        self.calls.push(CallInfo {
+            pc: 0,
+            sp,
+            retloc: sp,
+            closure,
+            program,
+            argc: 0,
+        });
+
+        self.run(depth)?;
+        Ok(std::mem::replace(&mut self.registers[sp], Value::Null))
+    }
+
+    /// Adds a function built directly from bytecode rather than compiled from
+    /// source, returning its id for use with `run_segment` or `Value::Func`.
+    /// Jump targets, constant indices, function ids and register operands are
+    /// checked up front, and `slots` must cover every register the bytecode
+    /// touches.
+    pub fn add_segment_with_bytecode(
+        &mut self,
+        name: String,
+        arity: Reg,
+        slots: Reg,
+        bytecode: Vec<Ins>,
+        constants: Vec<Value>,
+    ) -> Result<usize, error::Error> {
+        for (pc, ins) in bytecode.iter().enumerate() {
+            match *ins {
+                Ins::Jump(t)
+                | Ins::JumpFalse(_, t)
+                | Ins::JumpTrue(_, t)
+                | Ins::IterNext(_, t)
+                | Ins::Step(_, _, t)
+                    if t > bytecode.len() =>
+                {
+                    return error::Error::invalid_jump_target(pc, t).err()
+                }
+                Ins::LoadK(_, k) | Ins::CallMethod(_, k, _) if k as usize >= constants.len() => {
+                    return error::Error::invalid_operand(pc, k as usize).err()
+                }
+                Ins::LoadF(_, f) if f >= self.segments.len() => {
+                    return error::Error::invalid_operand(pc, f).err()
+                }
+                _ => {}
+            }
+
+            let end = ins.registers_end();
+            if end > slots as usize {
+                return error::Error::invalid_operand(pc, end - 1).err();
+            }
+        }
+
+        Ok(self.new_seg(Segment::new(
+            name,
+            false,
+            arity,
+            slots,
+            bytecode,
+            constants,
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            BTreeMap::new(),
+        )))
+    }
+
+    /// String form of a value in which objects defining a `__str__` function are
+    /// shown as its result. An object whose `__str__` is already running, such
+    /// as one converting itself, is shown as a plain object instead
+    pub fn to_display_string(&mut self, v: &Value) -> Result<String, error::Error> {
+        let mut hooks = vec![];
+        v.str_hooks(self, &mut HashSet::new(), &self.displaying, &mut hooks);
+
+        let mut hooked = HashMap::new();
+        for (p, f) in hooks {
+            self.displaying.insert(p);
+            let result = self.call_function(&f, &[Value::Object(p)]);
+            self.displaying.remove(&p);
+
+            let s = match result? {
+                Value::String(s) => s.to_string(),
+                v => self.to_display_string(&v)?,
+            };
+            hooked.insert(p, s);
+        }
+
+        Ok(v.to_string_hooked(self, &hooked))
+    }
+
+    /// Runs a function taking no arguments, such as one added with
+    /// `add_segment_with_bytecode`, returning its result
+    pub fn run_segment(&mut self, id: usize) -> Result<Value, error::Error> {
+        match self.segments.get(id) {
+            Some(_) => self.call_function(&Value::Func(id as u32, 0), &[]),
+            None => error::Error::invalid_operand(0, id).err(),
+        }
+    }
+
+    /// Calls a function value with the given arguments and runs it to completion,
+    /// allowing native functions to invoke callbacks.
+    ///
+    /// The callee's window is placed above all active frames and reserved until
+    /// it returns, so a native's own `arg0..arg0 + argc` registers stay intact
+    /// across callbacks, including when the callee is itself a native that calls
+    /// back again. Natives should still only hold register indices, never
+    /// references, as the register file may grow during the call. Heap values
+    /// created by the native and not yet stored in a register must be kept alive
+    /// with `push_root` while calling back.
+    pub fn call_function(&mut self, f: &Value, args: &[Value]) -> Result<Value, error::Error> {
+        let (program, closure) = match f {
+            Value::Func(program, closure) => (*program as usize, *closure),
+            v => return error::Error::uncallable_type(v).err(),
+        };
+
+        let depth = self.calls.len();
+        let base = self.stack_top();
+        let top = base + args.len() + 1;
+        if top >= self.registers.len() {
+            self.registers.resize(top + 1, Value::Null);
+        }
+
+        self.registers[base] = f.clone();
+        self.registers[base + 1..top].clone_from_slice(args);
+
+        let native_top = std::mem::replace(&mut self.native_top, top);
 
         self.calls.push(CallInfo {
             pc: 0,
-            sp: 0,
-            retloc: 0,
+            sp: base + 1,
+            retloc: base,
             closure,
             program,
+            argc: args.len(),
         });
 
-        'next_call: while let Some(mut ci) = self.calls.pop() {
+        let result = self
+            .run(depth)
+            .map(|_| std::mem::replace(&mut self.registers[base], Value::Null));
+
+        self.native_top = native_top;
+        self.registers[base..top].fill(Value::Null);
+        result
+    }
+
+    /// Runs frames until the call stack unwinds back to `depth`, on error the
+    /// trace is recorded and the unfinished frames are discarded
+    fn run(&mut self, depth: usize) -> Result<(), error::Error> {
+        let result = self.run_frames(depth);
+
+        if result.is_err() {
+            if self.trace.is_empty() {
+                self.trace = self.live_trace_pos();
+            }
+            self.calls.truncate(depth);
+        }
+
+        result
+    }
+
+    fn run_frames(&mut self, depth: usize) -> Result<(), error::Error> {
+        'next_call: while self.calls.len() > depth {
+            let mut ci = self.calls.pop().unwrap();
             let pg = &self.segments[ci.program];
 
             if let Some(function) = pg.native_function_pointer() {
-                let slots = pg.slots();
-                self.registers[ci.retloc] = function(self, ci.sp, slots as usize)
-                    .map_err(|e| e.with_pos(self.last_call_pos()))?;
+                // the native's frame is already popped, so reserve its arguments
+                // to keep them rooted should it collect
+                let (native_top, native_closure) = (self.native_top, self.native_closure);
+                self.native_top = native_top.max(ci.sp + ci.argc);
+                self.native_closure = ci.closure;
+
+                let result = function(self, ci.sp, ci.argc);
+                self.native_top = native_top;
+                self.native_closure = native_closure;
+
+                self.registers[ci.retloc] = result.map_err(|e| match e.pos {
+                    Some(_) => e,
+                    None => e.with_pos(self.last_call_pos()),
+                })?;
 
                 continue 'next_call;
             }
@@ -220,10 +882,25 @@ impl Env {
 
             let reg = &mut self.registers[ci.sp..bp];
             while ci.pc < pg.bytecode().len() {
+                if let Some(profile) = &mut self.profile {
+                    *profile.entry((ci.program, ci.pc)).or_insert(0) += 1;
+                }
+
                 match pg.bytecode()[ci.pc] {
                     Ins::Nop => {}
                     Ins::Not(a, b) => {
-                        reg[a as usize] = Value::Bool(!reg[b as usize].truthy());
+                        let v = &reg[b as usize];
+                        match Self::truthiness(&self.heap, self.empty_falsy, v) {
+                            Ok(truthy) => reg[a as usize] = Value::Bool(!truthy),
+                            Err(f) => {
+                                let v = v.clone();
+                                let (mut ci, truthy) = self.call_bool_hook(ci, f, v)?;
+                                self.registers[ci.sp + a as usize] = Value::Bool(!truthy);
+                                ci.pc += 1;
+                                self.calls.push(ci);
+                                continue 'next_call;
+                            }
+                        }
                     }
                     Ins::Neg(a, b) => {
                         reg[a as usize] =
@@ -241,15 +918,41 @@ impl Env {
                         reg[a as usize] = Value::Bool(reg[b as usize] != reg[c as usize])
                     }
                     Ins::Le(a, b, c) => {
-                        reg[a as usize] = Value::Bool(&reg[b as usize] <= &reg[c as usize])
+                        let ord = reg[b as usize]
+                            .compare(&reg[c as usize], Op::Le, &self.heap)
+                            .map_err(|e| e.with_pos(pg.get_pos(ci.pc)))?;
+                        reg[a as usize] =
+                            Value::Bool(matches!(ord, Some(Ordering::Less | Ordering::Equal)))
                     }
                     Ins::Lt(a, b, c) => {
-                        reg[a as usize] = Value::Bool(&reg[b as usize] < &reg[c as usize])
+                        let ord = reg[b as usize]
+                            .compare(&reg[c as usize], Op::Lt, &self.heap)
+                            .map_err(|e| e.with_pos(pg.get_pos(ci.pc)))?;
+                        reg[a as usize] = Value::Bool(ord == Some(Ordering::Less))
                     }
                     Ins::Add(a, b, c) => {
                         reg[a as usize] = (&reg[b as usize] + &reg[c as usize])
                             .map_err(|e| e.with_pos(pg.get_pos(ci.pc)))?;
                     }
+                    Ins::Concat(a, b, n) => {
+                        let operands = &reg[b as usize..(b + n) as usize];
+                        reg[a as usize] = if operands.iter().all(|v| matches!(v, Value::String(_)))
+                        {
+                            let mut out = String::new();
+                            for v in operands {
+                                if let Value::String(s) = v {
+                                    out.push_str(s);
+                                }
+                            }
+                            Value::String(Rc::new(out))
+                        } else {
+                            let mut acc = operands[0].clone();
+                            for v in &operands[1..] {
+                                acc = (&acc + v).map_err(|e| e.with_pos(pg.get_pos(ci.pc)))?;
+                            }
+                            acc
+                        };
+                    }
                     Ins::Sub(a, b, c) => {
                         reg[a as usize] = (&reg[b as usize] - &reg[c as usize])
                             .map_err(|e| e.with_pos(pg.get_pos(ci.pc)))?;
@@ -274,6 +977,11 @@ impl Env {
                         reg[a as usize] = (&reg[b as usize] >> &reg[c as usize])
                             .map_err(|e| e.with_pos(pg.get_pos(ci.pc)))?;
                     }
+                    Ins::Ushr(a, b, c) => {
+                        reg[a as usize] = reg[b as usize]
+                            .ushr(&reg[c as usize])
+                            .map_err(|e| e.with_pos(pg.get_pos(ci.pc)))?;
+                    }
                     Ins::BitAnd(a, b, c) => {
                         reg[a as usize] = (&reg[b as usize] & &reg[c as usize])
                             .map_err(|e| e.with_pos(pg.get_pos(ci.pc)))?;
@@ -298,9 +1006,15 @@ impl Env {
                     Ins::LoadB(a, b) => {
                         reg[a as usize] = Value::Bool(b);
                     }
+                    Ins::LoadI(a, i) => {
+                        reg[a as usize] = Value::Int(i as i64);
+                    }
                     Ins::LoadF(a, b) => {
                         reg[a as usize] = Value::Func(b as u32, 0);
                     }
+                    Ins::LoadSelf(a) => {
+                        reg[a as usize] = Value::Func(ci.program as u32, ci.closure);
+                    }
                     Ins::LoadG(a, b) => {
                         reg[a as usize] = self.globals[b as usize].clone();
                     }
@@ -313,36 +1027,98 @@ impl Env {
                     Ins::LoadK(a, b) => {
                         reg[a as usize] = pg.constant(b as usize).clone();
                     }
-                    Ins::JumpFalse(a, b) => {
-                        if !reg[a as usize].truthy() {
-                            ci.pc = b;
-                            continue;
+                    Ins::IterNext(a, b) => {
+                        let i = match reg[a as usize + 1] {
+                            Value::Int(i) => i as usize,
+                            _ => unreachable!("iterator index must be an integer"),
+                        };
+
+                        // strings are walked by byte offset rather than character
+                        // count so each step is constant time
+                        let next = match &reg[a as usize] {
+                            Value::Array(ptr) => match self.heap.access(*ptr) {
+                                HeapNode::Array { mark: _, vec } => {
+                                    vec.get(i).cloned().map(|v| (v, 1))
+                                }
+                                _ => unreachable!("value-pointer heap-object type mismatch"),
+                            },
+                            Value::String(s) => s[i..]
+                                .chars()
+                                .next()
+                                .map(|c| (Value::String(Rc::new(c.to_string())), c.len_utf8())),
+                            v => error::Error::not_iterable(v)
+                                .with_pos(pg.get_pos(ci.pc))
+                                .err()?,
+                        };
+
+                        match next {
+                            Some((v, step)) => {
+                                reg[a as usize + 1] = Value::Int((i + step) as i64);
+                                reg[a as usize + 2] = v;
+                            }
+                            None => {
+                                ci.pc = b;
+                                continue;
+                            }
                         }
                     }
-                    Ins::JumpTrue(a, b) => {
-                        if reg[a as usize].truthy() {
-                            ci.pc = b;
-                            continue;
+                    Ins::Step(a, b, t) => match (&reg[a as usize], &reg[b as usize]) {
+                        (Value::Int(i), Value::Int(step)) => match i.checked_add(*step) {
+                            Some(i) => reg[a as usize] = Value::Int(i),
+                            None => {
+                                ci.pc = t;
+                                continue;
+                            }
+                        },
+                        (v0, v1) => {
+                            reg[a as usize] =
+                                (v0 + v1).map_err(|e| e.with_pos(pg.get_pos(ci.pc)))?
+                        }
+                    },
+                    Ins::JumpFalse(a, b) | Ins::JumpTrue(a, b) => {
+                        let jump_if = matches!(pg.bytecode()[ci.pc], Ins::JumpTrue(..));
+                        let v = &reg[a as usize];
+                        match Self::truthiness(&self.heap, self.empty_falsy, v) {
+                            Ok(truthy) if truthy == jump_if => {
+                                ci.pc = b;
+                                continue;
+                            }
+                            Ok(_) => {}
+                            Err(f) => {
+                                let v = v.clone();
+                                let (mut ci, truthy) = self.call_bool_hook(ci, f, v)?;
+                                ci.pc = if truthy == jump_if { b } else { ci.pc + 1 };
+                                self.calls.push(ci);
+                                continue 'next_call;
+                            }
                         }
                     }
                     Ins::Jump(a) => {
                         ci.pc = a;
                         continue;
                     }
+                    Ins::Close(_, b, c) if c < b => {
+                        error::Error::invalid_register_range(b as usize, c as usize)
+                            .with_pos(pg.get_pos(ci.pc))
+                            .err()?
+                    }
                     Ins::Close(a, b, c) => match &reg[a as usize] {
                         Value::Func(program, _) => {
-                            reg[a as usize] = Value::Func(
-                                *program,
-                                self.heap.allocate(HeapNode::closure(
-                                    reg[b as usize..c as usize].to_vec(),
-                                )),
-                            );
+                            let program = *program;
+                            let node = HeapNode::closure(reg[b as usize..c as usize].to_vec());
+
+                            if self.gc_enabled && self.heap.should_collect() {
+                                self.collect_and_store(ci, a, node, |p| Value::Func(program, p));
+                                continue 'next_call;
+                            }
+
+                            reg[a as usize] = Value::Func(program, self.heap.allocate(node));
                         }
                         t0 => error::Error::uncallable_type(t0)
                             .with_pos(pg.get_pos(ci.pc))
                             .err()?,
                     },
-                    Ins::Call(a, b, c) => match &reg[b as usize] {
+                    Ins::Call(a, b, c, argc) => match &reg[b as usize] {
                         Value::Func(program, closure) => {
                             let sp = ci.sp + c as usize;
                             let retloc = ci.sp + a as usize;
@@ -354,7 +1130,8 @@ impl Env {
                                 sp,
                                 retloc,
                                 program: *program as usize,
-                                closure: *closure as usize,
+                                closure: *closure,
+                                argc: argc as usize,
                             });
                             continue 'next_call;
                         }
@@ -374,37 +1151,24 @@ impl Env {
                         continue 'next_call;
                     }
                     Ins::ObjNew(a) => {
-                        if self.heap.should_collect() {
-                            self.gc(0, 0)?;
-                            self.registers[ci.sp + a as usize] =
-                                Value::Object(self.heap.allocate(HeapNode::object(HashMap::new())));
+                        let node = HeapNode::object(ObjectMap::default());
 
-                            ci.pc += 1;
-                            self.calls.push(ci);
+                        if self.gc_enabled && self.heap.should_collect() {
+                            self.collect_and_store(ci, a, node, Value::Object);
                             continue 'next_call;
                         }
 
-                        reg[a as usize] =
-                            Value::Object(self.heap.allocate(HeapNode::object(HashMap::new())));
+                        reg[a as usize] = Value::Object(self.heap.allocate(node));
                     }
                     Ins::ArrNew(a, n) => {
-                        if self.heap.should_collect() {
-                            self.gc(0, 0)?;
-
-                            self.registers[ci.sp + a as usize] = Value::Array(
-                                self.heap
-                                    .allocate(HeapNode::array(vec![Value::Null; n as usize])),
-                            );
+                        let node = HeapNode::array(vec![Value::Null; n as usize]);
 
-                            ci.pc += 1;
-                            self.calls.push(ci);
+                        if self.gc_enabled && self.heap.should_collect() {
+                            self.collect_and_store(ci, a, node, Value::Array);
                             continue 'next_call;
                         }
 
-                        reg[a as usize] = Value::Array(
-                            self.heap
-                                .allocate(HeapNode::array(vec![Value::Null; n as usize])),
-                        );
+                        reg[a as usize] = Value::Array(self.heap.allocate(node));
                     }
                     Ins::ObjGet(a, b, c) => {
                         match &reg[b as usize] {
@@ -425,7 +1189,7 @@ impl Env {
                                         Value::Int(i) => error::Error::array_index_error(*i as u32)
                                             .with_pos(pg.get_pos(ci.pc))
                                             .err()?,
-                                        v => error::Error::type_error(&Value::Int(0), &v)
+                                        v => error::Error::type_error(&Value::Int(0), v)
                                             .with_pos(pg.get_pos(ci.pc))
                                             .err()?,
                                     },
@@ -442,16 +1206,77 @@ impl Env {
                                     Value::Int(i) => error::Error::array_index_error(*i as u32)
                                         .with_pos(pg.get_pos(ci.pc))
                                         .err()?,
-                                    v => error::Error::type_error(&Value::Int(0), &v)
+                                    v => error::Error::type_error(&Value::Int(0), v)
                                         .with_pos(pg.get_pos(ci.pc))
                                         .err()?,
                                 }
                             }
-                            v => error::Error::type_error_any(&v)
+                            v => error::Error::type_error_any(v)
                                 .with_pos(pg.get_pos(ci.pc))
                                 .err()?,
                         };
                     }
+                    Ins::Slice(a, b, c) => {
+                        let bounds = |len: usize| {
+                            slice_bounds(len, &reg[c as usize], &reg[c as usize + 1])
+                                .map_err(|e| e.with_pos(pg.get_pos(ci.pc)))
+                        };
+
+                        match &reg[b as usize] {
+                            Value::String(s) => {
+                                let (i, j) = bounds(s.chars().count())?;
+                                let sub: String = s.chars().skip(i).take(j - i).collect();
+                                reg[a as usize] = Value::String(Rc::new(sub));
+                            }
+                            Value::Array(ptr) => {
+                                let node = match self.heap.access(*ptr) {
+                                    HeapNode::Array { mark: _, vec } => {
+                                        let (i, j) = bounds(vec.len())?;
+                                        HeapNode::array(vec[i..j].to_vec())
+                                    }
+                                    _ => unreachable!("value-pointer heap-object type mismatch"),
+                                };
+
+                                if self.gc_enabled && self.heap.should_collect() {
+                                    self.collect_and_store(ci, a, node, Value::Array);
+                                    continue 'next_call;
+                                }
+
+                                reg[a as usize] = Value::Array(self.heap.allocate(node));
+                            }
+                            v => error::Error::type_error_any(v)
+                                .with_pos(pg.get_pos(ci.pc))
+                                .err()?,
+                        };
+                    }
+                    Ins::ObjGetStrict(a, b, c) => {
+                        let v = match &reg[b as usize] {
+                            Value::Object(ptr) => match self.heap.access(*ptr) {
+                                HeapNode::Object { mark: _, map } => {
+                                    map.get(&reg[c as usize]).cloned()
+                                }
+                                _ => unreachable!("value-pointer heap-object type mismatch"),
+                            },
+                            v => error::Error::type_error(&Value::Object(0), v)
+                                .with_pos(pg.get_pos(ci.pc))
+                                .err()?,
+                        };
+
+                        match v {
+                            Some(v) => reg[a as usize] = v,
+                            None => {
+                                // destructured keys are always string constants
+                                let k = reg[c as usize].clone();
+                                let key_repr = match &k {
+                                    Value::String(s) => format!("'{}'", s),
+                                    v => v.type_name().to_string(),
+                                };
+                                error::Error::missing_key(k, &key_repr)
+                                    .with_pos(pg.get_pos(ci.pc))
+                                    .err()?
+                            }
+                        }
+                    }
                     Ins::ObjIns(a, b, c) => {
                         let k = reg[b as usize].clone();
                         let v = reg[c as usize].clone();
@@ -467,6 +1292,11 @@ impl Env {
                                     Value::Int(i) if 0 <= i && (i as usize) < vec.len() => {
                                         vec[i as usize] = v
                                     }
+                                    Value::Int(i)
+                                        if self.append_on_assign && i as usize == vec.len() =>
+                                    {
+                                        vec.push(v)
+                                    }
                                     Value::Int(i) => error::Error::array_index_error(i as u32)
                                         .with_pos(pg.get_pos(ci.pc))
                                         .err()?,
@@ -476,7 +1306,116 @@ impl Env {
                                 },
                                 _ => unreachable!("value-pointer heap-object type mismatch"),
                             },
-                            v => error::Error::type_error_any(&v)
+                            v => error::Error::type_error_any(v)
+                                .with_pos(pg.get_pos(ci.pc))
+                                .err()?,
+                        }
+                    }
+                    Ins::ArrPush(a, b) => {
+                        let v = reg[b as usize].clone();
+                        match &reg[a as usize] {
+                            Value::Array(ptr) => match self.heap.access_mut(*ptr) {
+                                HeapNode::Array { mark: _, vec } => vec.push(v),
+                                _ => unreachable!("value-pointer heap-object type mismatch"),
+                            },
+                            v => error::Error::type_error(&Value::Array(0), v)
+                                .with_pos(pg.get_pos(ci.pc))
+                                .err()?,
+                        }
+                    }
+                    Ins::Spread(a, b) => match (&reg[a as usize], &reg[b as usize]) {
+                        (Value::Array(dst), Value::Array(src)) => {
+                            let vals = match self.heap.access(*src) {
+                                HeapNode::Array { mark: _, vec } => vec.clone(),
+                                _ => unreachable!("value-pointer heap-object type mismatch"),
+                            };
+
+                            match self.heap.access_mut(*dst) {
+                                HeapNode::Array { mark: _, vec } => vec.extend(vals),
+                                _ => unreachable!("value-pointer heap-object type mismatch"),
+                            }
+                        }
+                        (Value::Object(dst), Value::Object(src)) => {
+                            let entries = match self.heap.access(*src) {
+                                HeapNode::Object { mark: _, map } => map.clone(),
+                                _ => unreachable!("value-pointer heap-object type mismatch"),
+                            };
+
+                            match self.heap.access_mut(*dst) {
+                                HeapNode::Object { mark: _, map } => map.extend(entries),
+                                _ => unreachable!("value-pointer heap-object type mismatch"),
+                            }
+                        }
+                        (dst, src) => error::Error::type_error(dst, src)
+                            .with_pos(pg.get_pos(ci.pc))
+                            .err()?,
+                    },
+                    Ins::Unpack(a, n) => {
+                        let len = match &reg[a as usize] {
+                            Value::Array(ptr) => match self.heap.access(*ptr) {
+                                HeapNode::Array { mark: _, vec } => vec.len(),
+                                _ => unreachable!("value-pointer heap-object type mismatch"),
+                            },
+                            v => error::Error::unpack_type(v)
+                                .with_pos(pg.get_pos(ci.pc))
+                                .err()?,
+                        };
+
+                        if len != n as usize {
+                            error::Error::unpack_length(len, n as usize)
+                                .with_pos(pg.get_pos(ci.pc))
+                                .err()?
+                        }
+                    }
+                    Ins::CallMethod(a, k, argc) => {
+                        let key = pg.constant(k as usize);
+                        let receiver = &reg[a as usize + 1];
+                        let member = match receiver {
+                            Value::Object(ptr) => match self.heap.access(*ptr) {
+                                HeapNode::Object { mark: _, map } => map.get(key).cloned(),
+                                _ => unreachable!("value-pointer heap-object type mismatch"),
+                            },
+                            _ => None,
+                        };
+
+                        let method = self
+                            .methods
+                            .get(receiver.type_name())
+                            .and_then(|methods| methods.get(key));
+
+                        // object members are called without the receiver, methods with it
+                        let (f, offset, argc) = match (member, method) {
+                            (Some(f), _) => (f, a + 2, argc),
+                            (None, Some(f)) => (f.clone(), a + 1, argc + 1),
+                            (None, None) if matches!(receiver, Value::Object(_)) => {
+                                (Value::Null, a + 2, argc)
+                            }
+                            (None, None) => match key {
+                                Value::String(name) => error::Error::unknown_method(receiver, name)
+                                    .with_pos(pg.get_pos(ci.pc))
+                                    .err()?,
+                                _ => unreachable!("method name must be a string constant"),
+                            },
+                        };
+
+                        match f {
+                            Value::Func(program, closure) => {
+                                let sp = ci.sp + offset as usize;
+                                let retloc = ci.sp + a as usize;
+                                ci.pc += 1;
+
+                                self.calls.push(ci);
+                                self.calls.push(CallInfo {
+                                    pc: 0,
+                                    sp,
+                                    retloc,
+                                    program: program as usize,
+                                    closure,
+                                    argc: argc as usize,
+                                });
+                                continue 'next_call;
+                            }
+                            t0 => error::Error::uncallable_type(&t0)
                                 .with_pos(pg.get_pos(ci.pc))
                                 .err()?,
                         }
@@ -493,17 +1432,28 @@ impl Env {
                             retloc,
                             program: 1,
                             closure: 0,
+                            argc: 1,
                         });
                         continue 'next_call;
                     }
                 };
                 ci.pc += 1;
             }
+
+            // running off the end of a frame returns null and leaves its window cleared
+            reg.fill(Value::Null);
+            self.registers[ci.retloc] = Value::Null;
         }
         Ok(())
     }
 }
 
+impl From<ModuleFnRecord> for ModuleExport {
+    fn from(record: ModuleFnRecord) -> Self {
+        Self::Function(record)
+    }
+}
+
 impl ModuleFnRecord {
     pub fn new(name: String, arg_count: u16, function_pointer: NativeFnPtr) -> Self {
         Self {
@@ -513,3 +1463,16 @@ impl ModuleFnRecord {
         }
     }
 }
+
+/// Clamps the (possibly null) bounds of a slice expression to `0..=len`.
+fn slice_bounds(len: usize, start: &Value, end: &Value) -> Result<(usize, usize), error::Error> {
+    let clamp = |v: &Value, default: usize| match v {
+        Value::Null => Ok(default),
+        Value::Int(i) => Ok((*i).clamp(0, len as i64) as usize),
+        v => error::Error::type_error(&Value::Int(0), v).err(),
+    };
+
+    let i = clamp(start, 0)?;
+    let j = clamp(end, len)?;
+    Ok((i, j.max(i)))
+}
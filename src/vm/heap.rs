@@ -1,24 +1,20 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, DefaultHasher},
+};
 
-use crate::vm::Value;
+use crate::{error, vm::Value};
+
+/// Map type backing objects, hashed with a fixed seed so that iteration order
+/// is stable across runs
+pub type ObjectMap = HashMap<Value, Value, BuildHasherDefault<DefaultHasher>>;
 
 #[derive(Debug)]
 pub enum HeapNode {
-    Closure {
-        mark: bool,
-        vals: Vec<Value>,
-    },
-    Object {
-        mark: bool,
-        map: HashMap<Value, Value>,
-    },
-    Array {
-        mark: bool,
-        vec: Vec<Value>,
-    },
-    Free {
-        next: usize,
-    },
+    Closure { mark: bool, vals: Vec<Value> },
+    Object { mark: bool, map: ObjectMap },
+    Array { mark: bool, vec: Vec<Value> },
+    Free { next: usize },
 }
 
 impl HeapNode {
@@ -26,7 +22,7 @@ impl HeapNode {
         Self::Free { next }
     }
 
-    pub fn object(map: HashMap<Value, Value>) -> Self {
+    pub fn object(map: ObjectMap) -> Self {
         Self::Object { mark: false, map }
     }
 
@@ -38,6 +34,16 @@ impl HeapNode {
         Self::Closure { mark: false, vals }
     }
 
+    /// Kind of value the node backs, or `None` for free nodes
+    pub fn type_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Closure { mark: _, vals: _ } => Some("Closure"),
+            Self::Object { mark: _, map: _ } => Some("Object"),
+            Self::Array { mark: _, vec: _ } => Some("Array"),
+            Self::Free { next: _ } => None,
+        }
+    }
+
     pub fn mark(&mut self) {
         match self {
             Self::Closure { mark, vals: _ } => *mark = true,
@@ -67,7 +73,7 @@ impl HeapNode {
 
     pub fn children(&self) -> Vec<usize> {
         let get_ptr = |v: &Value| match v {
-            Value::Func(_, p) => Some(*p as usize),
+            Value::Func(_, p) => Some(*p),
             Value::Object(p) => Some(*p),
             Value::Array(p) => Some(*p),
             _ => None,
@@ -83,6 +89,10 @@ impl HeapNode {
 }
 
 pub trait Alloc<P> {
+    fn try_access(&self, ptr: P) -> Result<&HeapNode, error::Error>;
+
+    fn try_access_mut(&mut self, ptr: P) -> Result<&mut HeapNode, error::Error>;
+
     fn access(&self, ptr: P) -> &HeapNode;
 
     fn access_mut(&mut self, ptr: P) -> &mut HeapNode;
@@ -97,27 +107,91 @@ pub struct Heap {
     occupied: usize,
     head: usize,
     gc_threshold: usize,
+    grow_count: usize,
 }
 
 impl Heap {
     pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
         Self {
             head: 0,
             occupied: 0,
             nodes: (0..capacity).map(|i| HeapNode::free(i + 1)).collect(),
             gc_threshold: capacity / 2,
+            grow_count: 0,
         }
     }
 
-    pub fn mark(&mut self, ptr: usize) {
-        if self.nodes[ptr].marked() {
-            return;
+    pub fn capacity(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn occupied(&self) -> usize {
+        self.occupied
+    }
+
+    pub fn grow_count(&self) -> usize {
+        self.grow_count
+    }
+
+    /// Length of the free list, walked from `head`
+    pub fn free_count(&self) -> usize {
+        self.free_list().count()
+    }
+
+    /// Fraction of free nodes that sit below the last live node, where they
+    /// cannot be released by `compact`. 0.0 when every free node trails the
+    /// live ones or there are none.
+    pub fn fragmentation(&self) -> f64 {
+        let live_end = self
+            .nodes
+            .iter()
+            .rposition(|n| !matches!(n, HeapNode::Free { next: _ }))
+            .map_or(0, |p| p + 1);
+
+        let (interleaved, free) = self
+            .free_list()
+            .fold((0, 0), |(i, n), p| (i + (p < live_end) as usize, n + 1));
+
+        if free == 0 {
+            0.0
+        } else {
+            interleaved as f64 / free as f64
         }
+    }
 
-        self.nodes[ptr].mark();
+    fn free_list(&self) -> impl Iterator<Item = usize> + '_ {
+        // the list ends at the first index past the last node
+        let in_range = |p: &usize| *p < self.nodes.len();
+        std::iter::successors(Some(self.head).filter(in_range), move |p| {
+            match self.nodes[*p] {
+                HeapNode::Free { next } => Some(next).filter(in_range),
+                _ => unreachable!("free list should only link free nodes"),
+            }
+        })
+    }
 
-        for child in self.nodes[ptr].children() {
-            self.mark(child);
+    /// Number of occupied nodes of each kind, keyed by `HeapNode::type_name`
+    pub fn type_counts(&self) -> HashMap<&'static str, usize> {
+        let mut counts = HashMap::new();
+        for name in self.nodes.iter().filter_map(HeapNode::type_name) {
+            *counts.entry(name).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Marks every node reachable from `ptr`, using a worklist rather than
+    /// recursion so deeply nested structures cannot overflow the stack
+    pub fn mark(&mut self, ptr: usize) {
+        let mut pending = vec![ptr];
+
+        while let Some(ptr) = pending.pop() {
+            if self.nodes[ptr].marked() {
+                continue;
+            }
+
+            self.nodes[ptr].mark();
+            pending.extend(self.nodes[ptr].children());
         }
     }
 
@@ -133,6 +207,36 @@ impl Heap {
         self.gc_threshold = self.occupied * 2;
     }
 
+    /// Drops the free nodes trailing the last live node once fewer than a
+    /// quarter of the nodes are occupied. Live nodes never move as pointers are
+    /// indices, so the free list is rebuilt over the remaining nodes. Returns
+    /// the number of nodes released.
+    pub fn compact(&mut self) -> usize {
+        if self.occupied * 4 > self.nodes.len() {
+            return 0;
+        }
+
+        let len = self
+            .nodes
+            .iter()
+            .rposition(|n| !matches!(n, HeapNode::Free { next: _ }))
+            .map_or(1, |p| p + 1);
+        let released = self.nodes.len() - len;
+
+        self.nodes.truncate(len);
+        self.nodes.shrink_to_fit();
+
+        self.head = len;
+        for p in (0..len).rev() {
+            if let HeapNode::Free { next } = &mut self.nodes[p] {
+                *next = self.head;
+                self.head = p;
+            }
+        }
+
+        released
+    }
+
     pub fn should_collect(&self) -> bool {
         self.occupied >= self.gc_threshold
     }
@@ -144,10 +248,11 @@ impl Alloc<usize> for Heap {
             unreachable!("Cannot allocate a free node");
         }
 
-        let size = self.nodes.capacity();
+        let size = self.nodes.len();
         if self.head >= size {
             self.nodes
                 .extend((size..2 * size).map(|i| HeapNode::free(i + 1)));
+            self.grow_count += 1;
         }
 
         let ptr = self.head;
@@ -163,7 +268,7 @@ impl Alloc<usize> for Heap {
 
     fn deallocate(&mut self, ptr: usize) {
         match self.nodes[ptr] {
-            HeapNode::Free { next: _ } => return,
+            HeapNode::Free { next: _ } => (),
             _ => {
                 self.nodes[ptr] = HeapNode::free(self.head);
                 self.head = ptr;
@@ -172,11 +277,35 @@ impl Alloc<usize> for Heap {
         }
     }
 
+    fn try_access(&self, ptr: usize) -> Result<&HeapNode, error::Error> {
+        match self.nodes.get(ptr) {
+            Some(HeapNode::Free { next: _ }) | None => {
+                error::Error::invalid_heap_pointer(ptr).err()
+            }
+            Some(node) => Ok(node),
+        }
+    }
+
+    fn try_access_mut(&mut self, ptr: usize) -> Result<&mut HeapNode, error::Error> {
+        match self.nodes.get_mut(ptr) {
+            Some(HeapNode::Free { next: _ }) | None => {
+                error::Error::invalid_heap_pointer(ptr).err()
+            }
+            Some(node) => Ok(node),
+        }
+    }
+
     fn access(&self, ptr: usize) -> &HeapNode {
-        &self.nodes[ptr]
+        match self.try_access(ptr) {
+            Ok(node) => node,
+            Err(e) => panic!("{}", e.msg),
+        }
     }
 
     fn access_mut(&mut self, ptr: usize) -> &mut HeapNode {
-        &mut self.nodes[ptr]
+        match self.try_access_mut(ptr) {
+            Ok(node) => node,
+            Err(e) => panic!("{}", e.msg),
+        }
     }
 }
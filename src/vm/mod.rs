@@ -4,6 +4,7 @@ mod segment;
 mod value;
 
 pub use env::Env;
+pub use env::ModuleExport;
 pub use env::ModuleFnRecord;
 pub use segment::NativeFnPtr;
 pub use segment::Segment;
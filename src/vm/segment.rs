@@ -16,6 +16,7 @@ pub type NativeFnPtr = fn(&mut Env, usize, usize) -> Result<Value, error::Error>
 pub struct Segment {
     name: String,
     global: bool,
+    arity: Reg,
     slots: Reg,
     bytecode: Vec<Ins>,
     constants: Vec<Value>,
@@ -24,12 +25,15 @@ pub struct Segment {
     positions: BTreeMap<usize, io::Pos>,
     parent: Option<usize>,
     native: Option<NativeFnPtr>,
+    pos: Option<io::Pos>,
 }
 
 impl Segment {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: String,
         global: bool,
+        arity: Reg,
         slots: Reg,
         bytecode: Vec<Ins>,
         constants: Vec<Value>,
@@ -41,6 +45,7 @@ impl Segment {
         Self {
             name,
             global,
+            arity,
             slots,
             bytecode,
             constants,
@@ -49,6 +54,7 @@ impl Segment {
             positions,
             parent,
             native: None,
+            pos: None,
         }
     }
 
@@ -56,6 +62,7 @@ impl Segment {
         Self {
             name,
             global,
+            arity: 0,
             slots: 0,
             bytecode: vec![],
             constants: vec![],
@@ -64,6 +71,7 @@ impl Segment {
             positions: BTreeMap::new(),
             parent: None,
             native: None,
+            pos: None,
         }
     }
 
@@ -71,6 +79,7 @@ impl Segment {
         Self {
             name,
             global: false,
+            arity: args,
             slots: args,
             bytecode: vec![],
             constants: vec![],
@@ -79,9 +88,20 @@ impl Segment {
             positions: BTreeMap::new(),
             parent: None,
             native: Some(native),
+            pos: None,
         }
     }
 
+    /// Sets the source position the function was declared at
+    pub fn with_pos(mut self, pos: io::Pos) -> Self {
+        self.pos = Some(pos);
+        self
+    }
+
+    pub fn pos(&self) -> Option<&io::Pos> {
+        self.pos.as_ref()
+    }
+
     pub fn clear_definition(&mut self) {
         self.bytecode.clear();
         self.positions.clear();
@@ -109,6 +129,10 @@ impl Segment {
         self.bytecode.len()
     }
 
+    pub fn arity(&self) -> Reg {
+        self.arity
+    }
+
     pub fn slots(&self) -> Reg {
         self.slots
     }
@@ -193,7 +217,7 @@ impl Segment {
     }
 
     pub fn get_symbol(&self, id: &String) -> Option<Reg> {
-        self.symbols.get(id).map(|r| *r)
+        self.symbols.get(id).copied()
     }
 
     pub fn new_upval(&mut self, id: String) -> Option<Reg> {
@@ -207,7 +231,7 @@ impl Segment {
     }
 
     pub fn get_upval(&self, id: &String) -> Option<Reg> {
-        self.up_values.get(id).map(|r| *r)
+        self.up_values.get(id).copied()
     }
 
     pub fn storek(&mut self, v: Value) -> Reg {
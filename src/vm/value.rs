@@ -1,5 +1,6 @@
 use std::{
-    collections::HashSet,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     hash::{Hash, Hasher},
     ops,
     rc::Rc,
@@ -9,7 +10,7 @@ use crate::{error, frontend::operator};
 
 use super::{
     env::Env,
-    heap::{Alloc, HeapNode},
+    heap::{Alloc, Heap, HeapNode},
 };
 
 #[derive(PartialEq, Debug, Clone)]
@@ -19,6 +20,8 @@ pub enum Value {
     Float(f64),
     Bool(bool),
     String(Rc<String>),
+    /// Segment id and closure pointer, functions compare by identity so copies of
+    /// a function value are equal while separately created closures are not
     Func(u32, usize),
     Object(usize),
     Array(usize),
@@ -32,7 +35,7 @@ impl Value {
             Value::Float(v) => *v != 0.0,
             Value::Bool(v) => *v,
             Value::Func(_, _) => true,
-            Value::String(v) => v.len() > 0,
+            Value::String(v) => !v.is_empty(),
             Value::Object(_) => true,
             Value::Array(_) => true,
         }
@@ -51,6 +54,29 @@ impl Value {
         }
     }
 
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::Int(_) | Value::Float(_))
+    }
+
+    /// Numeric value promoted to a float, or `None` for non-numeric types
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(v) => Some(*v as f64),
+            Value::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Numeric value truncated to an integer, or `None` for non-numeric types
+    /// and floats that cannot be represented
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(v) => Some(*v),
+            Value::Float(v) if v.is_finite() => Some(v.trunc() as i64),
+            _ => None,
+        }
+    }
+
     pub fn bit_flip(&self) -> Result<Self, error::Error> {
         match self {
             Value::Int(v) => Ok(Value::Int(!v)),
@@ -58,26 +84,102 @@ impl Value {
         }
     }
 
+    /// Logical right shift, treating the integer as unsigned so zeros are shifted in
+    pub fn ushr(&self, rhs: &Value) -> Result<Self, error::Error> {
+        match (self, rhs) {
+            (Value::Int(v0), Value::Int(v1)) if *v1 >= 0 => Ok(Value::Int(
+                u32::try_from(*v1)
+                    .ok()
+                    .and_then(|v1| (*v0 as u64).checked_shr(v1))
+                    .unwrap_or(0) as i64,
+            )),
+            (Value::Int(_), Value::Int(v1)) => error::Error::negative_shift(*v1).err(),
+            (t0, t1) => error::Error::op_type_mismatch(operator::Op::Ushr, t0, t1).err(),
+        }
+    }
+
     pub fn from_string(s: &str) -> Value {
         Value::String(Rc::new(s.to_string()))
     }
 
     pub fn repr(&self, env: &Env) -> String {
-        self.repr_safe(env, &mut HashSet::new())
+        self.repr_safe(env, &mut HashSet::new(), usize::MAX, &HashMap::new())
     }
 
-    fn repr_safe(&self, env: &Env, visited: &mut HashSet<usize>) -> String {
+    /// Representation that shows at most `limit` elements of each array or object,
+    /// followed by a count of the elements left out
+    pub fn summary(&self, env: &Env, limit: usize) -> String {
+        self.repr_safe(env, &mut HashSet::new(), limit, &HashMap::new())
+    }
+
+    fn repr_safe(
+        &self,
+        env: &Env,
+        visited: &mut HashSet<usize>,
+        limit: usize,
+        hooked: &HashMap<usize, String>,
+    ) -> String {
         match self {
             Value::String(v) => format!("'{}'", v),
-            _ => self.to_string_safe(env, visited),
+            Value::Float(v) if v.fract() == 0.0 => format!("{:.1}", v),
+            _ => self.to_string_safe(env, visited, limit, hooked),
         }
     }
 
     pub fn to_string(&self, env: &Env) -> String {
-        self.to_string_safe(env, &mut HashSet::new())
+        self.to_string_safe(env, &mut HashSet::new(), usize::MAX, &HashMap::new())
+    }
+
+    /// String form in which each object found in `hooked` is shown as its
+    /// precomputed text, used to display objects with a `__str__` function
+    pub(crate) fn to_string_hooked(&self, env: &Env, hooked: &HashMap<usize, String>) -> String {
+        self.to_string_safe(env, &mut HashSet::new(), usize::MAX, hooked)
+    }
+
+    /// Collects the objects reachable from this value that define a `__str__`
+    /// function, along with that function, without looking inside them
+    pub(crate) fn str_hooks(
+        &self,
+        env: &Env,
+        visited: &mut HashSet<usize>,
+        skip: &HashSet<usize>,
+        hooks: &mut Vec<(usize, Value)>,
+    ) {
+        let p = match self {
+            Value::Array(p) | Value::Object(p) if visited.insert(*p) => *p,
+            _ => return,
+        };
+
+        match env.heap.access(p) {
+            HeapNode::Array { mark: _, vec } => vec
+                .iter()
+                .for_each(|v| v.str_hooks(env, visited, skip, hooks)),
+            HeapNode::Object { mark: _, map } => match map.get(&Value::from_string("__str__")) {
+                Some(f @ Value::Func(..)) if !skip.contains(&p) => hooks.push((p, f.clone())),
+                _ => map.iter().for_each(|(k, v)| {
+                    k.str_hooks(env, visited, skip, hooks);
+                    v.str_hooks(env, visited, skip, hooks);
+                }),
+            },
+            _ => unreachable!("value-pointer heap-object type mismatch"),
+        }
+    }
+
+    fn join_limited(items: impl Iterator<Item = String>, len: usize, limit: usize) -> String {
+        let mut parts = items.take(limit).collect::<Vec<String>>();
+        if len > limit {
+            parts.push(format!("... (+{} more)", len - limit));
+        }
+        parts.join(", ")
     }
 
-    fn to_string_safe(&self, env: &Env, visited: &mut HashSet<usize>) -> String {
+    fn to_string_safe(
+        &self,
+        env: &Env,
+        visited: &mut HashSet<usize>,
+        limit: usize,
+        hooked: &HashMap<usize, String>,
+    ) -> String {
         match self {
             Value::Null => "null".to_string(),
             Value::Int(v) => format!("{}", v),
@@ -90,15 +192,20 @@ impl Value {
             }
             Value::Array(v) if visited.contains(v) => "[...]".to_string(),
             Value::Object(v) if visited.contains(v) => "{ ... }".to_string(),
+            Value::Object(v) if hooked.contains_key(v) => hooked[v].clone(),
+            Value::Object(v) if env.module_name(*v).is_some() => {
+                format!("<module '{}'>", env.module_name(*v).unwrap())
+            }
             Value::Array(v) => {
                 visited.insert(*v);
                 match env.heap.access(*v) {
                     HeapNode::Array { mark: _, vec } => format!(
                         "[{}]",
-                        vec.iter()
-                            .map(|v| v.repr_safe(env, visited))
-                            .collect::<Vec<String>>()
-                            .join(", ")
+                        Self::join_limited(
+                            vec.iter().map(|v| v.repr_safe(env, visited, limit, hooked)),
+                            vec.len(),
+                            limit
+                        )
                     ),
                     _ => unreachable!("value-pointer heap-object type mismatch"),
                 }
@@ -108,14 +215,15 @@ impl Value {
                 match env.heap.access(*v) {
                     HeapNode::Object { mark: _, map } => format!(
                         "{{ {} }}",
-                        map.iter()
-                            .map(|(k, v)| format!(
+                        Self::join_limited(
+                            map.iter().map(|(k, v)| format!(
                                 "{}: {}",
-                                k.repr_safe(env, visited),
-                                v.repr_safe(env, visited)
-                            ))
-                            .collect::<Vec<String>>()
-                            .join(", ")
+                                k.repr_safe(env, visited, limit, hooked),
+                                v.repr_safe(env, visited, limit, hooked)
+                            )),
+                            map.len(),
+                            limit
+                        )
                     ),
                     _ => unreachable!("value-pointer heap-object type mismatch"),
                 }
@@ -123,6 +231,104 @@ impl Value {
         }
     }
 
+    /// Ordering used by comparison operators, numbers of either type compare by
+    /// value while other types only compare with their own type. Arrays compare
+    /// lexicographically by their elements. `None` is returned for comparable
+    /// values without an order, such as NaN
+    pub fn compare(
+        &self,
+        other: &Value,
+        op: operator::Op,
+        heap: &Heap,
+    ) -> Result<Option<Ordering>, error::Error> {
+        self.compare_safe(other, op, heap, &mut HashSet::new())
+    }
+
+    fn compare_safe(
+        &self,
+        other: &Value,
+        op: operator::Op,
+        heap: &Heap,
+        visited: &mut HashSet<(usize, usize)>,
+    ) -> Result<Option<Ordering>, error::Error> {
+        match (self, other) {
+            // arrays already being compared further up are treated as equal
+            (Value::Array(p0), Value::Array(p1)) if p0 == p1 || !visited.insert((*p0, *p1)) => {
+                Ok(Some(Ordering::Equal))
+            }
+            (Value::Array(p0), Value::Array(p1)) => match (heap.access(*p0), heap.access(*p1)) {
+                (HeapNode::Array { mark: _, vec: v0 }, HeapNode::Array { mark: _, vec: v1 }) => {
+                    for (e0, e1) in v0.iter().zip(v1.iter()) {
+                        match e0.compare_safe(e1, op, heap, visited)? {
+                            Some(Ordering::Equal) => {}
+                            ord => return Ok(ord),
+                        }
+                    }
+                    Ok(Some(v0.len().cmp(&v1.len())))
+                }
+                _ => unreachable!("value-pointer heap-object type mismatch"),
+            },
+            (Value::Int(v0), Value::Int(v1)) => Ok(v0.partial_cmp(v1)),
+            (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+                Ok(self.as_f64().unwrap().partial_cmp(&other.as_f64().unwrap()))
+            }
+            (Value::Null, Value::Null)
+            | (Value::Bool(_), Value::Bool(_))
+            | (Value::String(_), Value::String(_)) => Ok(self.partial_cmp(&other)),
+            (t0, t1) => error::Error::op_type_mismatch(op, t0, t1).err(),
+        }
+    }
+
+    /// Structural equality, comparing arrays and objects by their contents
+    pub fn deep_eq(&self, other: &Value, env: &Env) -> bool {
+        self.deep_eq_safe(other, env, &mut HashSet::new())
+    }
+
+    fn deep_eq_safe(
+        &self,
+        other: &Value,
+        env: &Env,
+        visited: &mut HashSet<(usize, usize)>,
+    ) -> bool {
+        match (self, other) {
+            (Value::Array(p0), Value::Array(p1)) | (Value::Object(p0), Value::Object(p1))
+                if p0 == p1 || !visited.insert((*p0, *p1)) =>
+            {
+                true
+            }
+            (Value::Array(p0), Value::Array(p1)) => {
+                match (env.heap.access(*p0), env.heap.access(*p1)) {
+                    (
+                        HeapNode::Array { mark: _, vec: v0 },
+                        HeapNode::Array { mark: _, vec: v1 },
+                    ) => {
+                        v0.len() == v1.len()
+                            && v0
+                                .iter()
+                                .zip(v1.iter())
+                                .all(|(a, b)| a.deep_eq_safe(b, env, visited))
+                    }
+                    _ => unreachable!("value-pointer heap-object type mismatch"),
+                }
+            }
+            (Value::Object(p0), Value::Object(p1)) => {
+                match (env.heap.access(*p0), env.heap.access(*p1)) {
+                    (
+                        HeapNode::Object { mark: _, map: m0 },
+                        HeapNode::Object { mark: _, map: m1 },
+                    ) => {
+                        m0.len() == m1.len()
+                            && m0.iter().all(|(k, a)| {
+                                m1.get(k).is_some_and(|b| a.deep_eq_safe(b, env, visited))
+                            })
+                    }
+                    _ => unreachable!("value-pointer heap-object type mismatch"),
+                }
+            }
+            (v0, v1) => v0 == v1,
+        }
+    }
+
     pub fn length(&self, env: &Env) -> Result<usize, error::Error> {
         match self {
             Value::String(v) => Ok(v.len()),
@@ -132,7 +338,7 @@ impl Value {
                 _ => unreachable!("value-pointer heap-object type mismatch"),
             },
             Value::Null => error::Error::unexpected_null().err(),
-            t1 => error::Error::type_error(self, t1).err(),
+            v => error::Error::no_length(v).err(),
         }
     }
 }
@@ -216,6 +422,7 @@ impl ops::BitAnd<&Value> for &Value {
     fn bitand(self, rhs: &Value) -> Self::Output {
         match (self, rhs) {
             (Value::Int(v0), Value::Int(v1)) => Ok(Value::Int(v0.bitand(*v1))),
+            (Value::Bool(v0), Value::Bool(v1)) => Ok(Value::Bool(v0.bitand(*v1))),
             (t0, t1) => error::Error::op_type_mismatch(operator::Op::BitAnd, t0, t1).err(),
         }
     }
@@ -226,6 +433,7 @@ impl ops::BitOr<&Value> for &Value {
     fn bitor(self, rhs: &Value) -> Self::Output {
         match (self, rhs) {
             (Value::Int(v0), Value::Int(v1)) => Ok(Value::Int(v0.bitor(*v1))),
+            (Value::Bool(v0), Value::Bool(v1)) => Ok(Value::Bool(v0.bitor(*v1))),
             (t0, t1) => error::Error::op_type_mismatch(operator::Op::BitOr, t0, t1).err(),
         }
     }
@@ -236,6 +444,7 @@ impl ops::BitXor<&Value> for &Value {
     fn bitxor(self, rhs: &Value) -> Self::Output {
         match (self, rhs) {
             (Value::Int(v0), Value::Int(v1)) => Ok(Value::Int(v0.bitxor(*v1))),
+            (Value::Bool(v0), Value::Bool(v1)) => Ok(Value::Bool(v0.bitxor(*v1))),
             (t0, t1) => error::Error::op_type_mismatch(operator::Op::BitXor, t0, t1).err(),
         }
     }
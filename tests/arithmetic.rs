@@ -347,6 +347,31 @@ pub fn test_right_shift() {
     assert_eq!(result.unwrap(), Value::Int(2))
 }
 
+#[test]
+pub fn test_logical_right_shift() {
+    let result = Interpreter::new(false, false, vec![]).evaluate_from_string("-8 >> 1");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(-4));
+
+    let result = Interpreter::new(false, false, vec![]).evaluate_from_string("-8 >>> 1");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(i64::MAX - 3));
+
+    let result = Interpreter::new(false, false, vec![]).evaluate_from_string("-1 >>> 60");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(15));
+}
+
+#[test]
+pub fn test_logical_right_shift_negative() {
+    let result = Interpreter::new(false, false, vec![]).evaluate_from_string("8 >>> -1");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(
+        result.unwrap_err().err_type,
+        ErrorType::ArithmeticError(Value::Int(-1))
+    );
+}
+
 #[test]
 pub fn test_bitwise_and_zero() {
     let result = Interpreter::new(false, false, vec![]).evaluate_from_string("5 & 0");
@@ -367,3 +392,123 @@ pub fn test_bitwise_xor_same_number() {
     assert!(result.is_ok(), "Expression should succeed");
     assert_eq!(result.unwrap(), Value::Int(0));
 }
+
+#[test]
+pub fn test_incomparable_types() {
+    for expr in [
+        "\"a\" < 1",
+        "1 >= \"a\"",
+        "null <= 0",
+        "{} > {}",
+        "[1, 2] < [1, \"a\"]",
+        "[1] < 1",
+        "true < 1",
+    ] {
+        let result = Interpreter::new(false, false, vec![]).evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should not succeed");
+        assert!(matches!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(_)
+        ));
+    }
+}
+
+#[test]
+pub fn test_mixed_numeric_comparison() {
+    for (expr, expected) in [
+        ("1 < 2", true),
+        ("1 < 1.5", true),
+        ("2.5 >= 2", true),
+        ("2 <= 2.0", true),
+        ("\"a\" < \"b\"", true),
+    ] {
+        let result = Interpreter::new(false, false, vec![]).evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::Bool(expected));
+    }
+}
+
+#[test]
+pub fn test_chained_string_concatenation() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let result = nsi.evaluate_from_string("\"a\" + \"b\" + \"c\" + \"d\"");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("abcd"));
+
+    let result = nsi.evaluate_from_string("1 + 2 + 3.5 + 4");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Float(10.5));
+
+    let result = nsi.evaluate_from_string("\"a\" + \"b\" + 1");
+    assert!(result.is_err(), "Expression should fail");
+    assert!(matches!(
+        result.unwrap_err().err_type,
+        ErrorType::TypeError(_)
+    ));
+}
+
+#[test]
+pub fn test_long_string_concatenation_chain() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let chain = (0..500)
+        .map(|i| format!("\"{}\"", i % 10))
+        .collect::<Vec<_>>()
+        .join(" + ");
+    let program = format!(
+        "let s = \"\"; let i = 0; while i < 200 {{ s = {}; i += 1; }}",
+        chain
+    );
+
+    let state = nsi.execute_from_string(&program);
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let expected = (0..500).map(|i| (i % 10).to_string()).collect::<String>();
+    let s = nsi.environment().get_global(&"s".to_string());
+    assert_eq!(s.unwrap(), &Value::from_string(&expected));
+}
+
+#[test]
+pub fn test_bitwise_booleans() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    for (expr, expected) in [
+        ("true & false", false),
+        ("true & true", true),
+        ("true | false", true),
+        ("false | false", false),
+        ("true ^ true", false),
+        ("true ^ false", true),
+        ("(true & false) == false", true),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::Bool(expected), "{}", expr);
+    }
+
+    for expr in ["true & 1", "0 | false", "true ^ 1.0"] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+        assert!(matches!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(_)
+        ));
+    }
+}
+
+#[test]
+pub fn test_array_comparison() {
+    for (expr, expected) in [
+        ("[1, 2] < [1, 3]", true),
+        ("[1, 2] < [1, 2, 0]", true),
+        ("[2] > [1, 9]", true),
+        ("[] >= []", true),
+        ("[\"a\", [1.5]] <= [\"a\", [2]]", true),
+        ("[1, \"x\"] < [0, 5]", false),
+    ] {
+        let result = Interpreter::new(false, false, vec![]).evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::Bool(expected));
+    }
+}
@@ -0,0 +1,83 @@
+use std::process::Command;
+
+use clap::Parser;
+use ns::{run_cli, Cli};
+
+fn run_script(name: &str, source: &str) -> i32 {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, source).unwrap();
+
+    let status = run_cli(Cli::parse_from(["ns", "run", path.to_str().unwrap()]));
+    std::fs::remove_file(&path).unwrap();
+
+    status
+}
+
+#[test]
+pub fn test_cli_exit_status() {
+    assert_eq!(run_script("ns_cli_ok.ns", "let x = 1;"), 0);
+    assert_eq!(run_script("ns_cli_return.ns", "return 3;"), 3);
+    assert_eq!(run_script("ns_cli_return_max.ns", "return 255;"), 255);
+    assert_eq!(
+        run_script("ns_cli_return_float.ns", "return 2.5;"),
+        0,
+        "Only integer results should set the exit code"
+    );
+}
+
+#[test]
+pub fn test_cli_exit_status_invalid() {
+    assert_eq!(
+        run_script("ns_cli_return_large.ns", "return 256;"),
+        1,
+        "Exit codes above a byte should fail"
+    );
+    assert_eq!(
+        run_script("ns_cli_return_negative.ns", "return -1;"),
+        1,
+        "Negative exit codes should fail"
+    );
+}
+
+#[test]
+pub fn test_cli_error_status() {
+    assert_eq!(run_script("ns_cli_runtime_error.ns", "let x = 1 / 0;"), 1);
+    assert_eq!(run_script("ns_cli_syntax_error.ns", "let = ;"), 1);
+    assert_eq!(
+        run_script("ns_cli_error.ns", "import(\"std\").error(\"failed\");"),
+        1
+    );
+    assert_eq!(
+        run_script("ns_cli_assert_ok.ns", "import(\"test\").assertTrue(2 > 1);"),
+        0
+    );
+    assert_eq!(
+        run_script("ns_cli_assert.ns", "import(\"test\").assertTrue(1 > 2);"),
+        1
+    );
+}
+
+#[test]
+pub fn test_cli_eval_exit_status() {
+    assert_eq!(run_cli(Cli::parse_from(["ns", "eval", "1 + 2"])), 0);
+    assert_eq!(run_cli(Cli::parse_from(["ns", "eval", "1 + null"])), 1);
+}
+
+#[test]
+pub fn test_cli_binary_exit_status() {
+    let path = std::env::temp_dir().join("ns_cli_binary.ns");
+    std::fs::write(&path, "return 3;").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ns"))
+        .arg("run")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        output.status.code(),
+        Some(3),
+        "The binary should exit with the status of the script"
+    );
+}
@@ -158,11 +158,245 @@ pub fn test_function_un_callable() {
 }
 
 #[test]
-pub fn test_function_return_invalid_position() {
+pub fn test_top_level_return() {
     let mut nsi = Interpreter::new(false, false, vec![]);
 
     let state = nsi.execute_from_string("return;");
-    assert!(state.is_err(), "Statement should fail");
+    assert_eq!(state.unwrap(), Value::Null);
 
-    assert_eq!(state.unwrap_err().err_type, ErrorType::SyntaxError);
+    let state = nsi.execute_from_string("let x = 1;");
+    assert_eq!(state.unwrap(), Value::Null);
+
+    let state = nsi.execute_from_string(
+        "let after = false;
+        for i in 0..10 { if i == 6 { return i * 7; } }
+        after = true;",
+    );
+    assert_eq!(state.unwrap(), Value::Int(42));
+    assert_eq!(
+        nsi.environment().get_global(&"after".to_string()).unwrap(),
+        &Value::Bool(false)
+    );
+
+    let path = std::env::temp_dir().join("ns_test_top_level_return.ns");
+    std::fs::write(&path, "fun answer() { return 40; }\nreturn answer() + 2;\n").unwrap();
+    let state = nsi.execute_from_file(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(state.unwrap(), Value::Int(42));
+}
+
+#[test]
+pub fn test_closure_multiple_upvalues() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "fun outer(a, b) { let c = 3; return fun(x) { return a * 100 + b * 10 + c + x; }; } _ = outer(1, 2)(4);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(127));
+
+    let state = nsi.execute_from_string(
+        "fun nest(a, b) { return fun(c) { return fun(d) { return a - b + c * d; }; }; } _ = nest(10, 2)(3)(4);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(20));
+}
+
+#[test]
+pub fn test_closure_without_upvalues() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state =
+        nsi.execute_from_string("fun outer(a) { return fun(x) { return x; }; } _ = outer(1);");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert!(matches!(val.unwrap(), &Value::Func(_, 0)));
+}
+
+#[test]
+pub fn test_local_recursive_function() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "fun outer(n) { fun fact(k) { if k <= 1 { return 1; } return k * fact(k - 1); } return fact(n); } _ = outer(5);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(120));
+}
+
+#[test]
+pub fn test_local_recursive_closure() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "fun outer(n) { fun count(k) { if k <= 0 { return n; } return count(k - 1) + 1; } return count(3); } _ = outer(10);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(13));
+}
+
+#[test]
+pub fn test_function_terminal_if_else_returns() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "fun sign(x) { if x < 0 { return -1; } else if x == 0 { return 0; } else { return 1; } }",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for (arg, expected) in [(-5, -1), (0, 0), (7, 1)] {
+        let val = nsi.evaluate_from_string(&format!("sign({})", arg));
+        assert!(val.is_ok(), "Expression should succeed");
+        assert_eq!(val.unwrap(), Value::Int(expected));
+    }
+}
+
+#[test]
+pub fn test_function_terminal_if_without_else() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string("fun f(x) { if x { return 1; } } _ = [f(true), f(false)];");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.evaluate_from_string("_[0] == 1 && _[1] == null");
+    assert!(val.is_ok(), "Expression should succeed");
+    assert_eq!(val.unwrap(), Value::Bool(true));
+}
+
+#[test]
+pub fn test_global_function_capturing_global() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let base = 10; fun make(n) { return fun(x) { return x * n + base; }; } let f = make(3); base = 20; _ = f(2);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(26));
+}
+
+#[test]
+pub fn test_function_identity_equality() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "fun f(x) { return x; } let a = f; let b = a;
+        fun make(n) { return fun() { return n; }; } let c = make(1); let d = c; let e = make(1);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("a == b", true),
+        ("a == f", true),
+        ("c == d", true),
+        ("c == e", false),
+        ("c != e", true),
+        ("f == make", false),
+    ] {
+        let val = nsi.evaluate_from_string(expr);
+        assert!(val.is_ok(), "Expression should succeed");
+        assert_eq!(val.unwrap(), Value::Bool(expected));
+    }
+}
+
+#[test]
+pub fn test_function_multiple_return_values() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "fun divmod(a, b) { return a / b, a % b; }
+        fun f() { let (q, r) = divmod(17, 5); return q * 10 + r; }
+        let (q, r) = divmod(9, 4);
+        let n = f();",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(env.get_global(&"q".to_string()).unwrap(), &Value::Int(2));
+    assert_eq!(env.get_global(&"r".to_string()).unwrap(), &Value::Int(1));
+    assert_eq!(env.get_global(&"n".to_string()).unwrap(), &Value::Int(32));
+}
+
+#[test]
+pub fn test_destructure_mismatch() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    for program in [
+        "let (a, b) = [1, 2, 3];",
+        "let (c, d) = 5;",
+        "for (e, f) in [[1]] {}",
+    ] {
+        let state = nsi.execute_from_string(program);
+        assert!(state.is_err(), "Statement should fail");
+        assert_eq!(state.unwrap_err().err_type, ErrorType::ValueError);
+    }
+}
+
+#[test]
+pub fn test_call_on_expressions() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let a = (fun(x) { return x * 2; })(5);
+        fun getFn() { return fun(y) { return y + 1; }; }
+        let b = getFn()(2);
+        let c = [fun() { return 7; }][0]();
+        let d = 0;
+        fun() { d = 4; }();
+        (fun(n) { d += n; })(1);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(env.get_global(&"a".to_string()).unwrap(), &Value::Int(10));
+    assert_eq!(env.get_global(&"b".to_string()).unwrap(), &Value::Int(3));
+    assert_eq!(env.get_global(&"c".to_string()).unwrap(), &Value::Int(7));
+    assert_eq!(env.get_global(&"d".to_string()).unwrap(), &Value::Int(5));
+
+    let result = nsi.evaluate_from_string("(fun(f) { return f; })(getFn)()(41)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(42));
+}
+
+#[test]
+pub fn test_closure_called_through_object_field() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "fun counter(start) {
+            let box = [start];
+            return fun() { box[0] += 1; return box[0]; };
+        }
+        fun adder(n) { return fun(x) { return x + n; }; }
+        let obj = {\"next\": counter(10), \"add\": adder(5), \"nested\": {\"add\": adder(7)}};
+        let std = import(\"std\");
+        std.inc = adder(1);
+        fun local() { let o = {\"f\": adder(100)}; return o.f(1); }",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("obj.next()", 11),
+        ("obj.next()", 12),
+        ("obj[\"next\"]()", 13),
+        ("obj.add(1)", 6),
+        ("obj.nested.add(1)", 8),
+        ("std.inc(1)", 2),
+        ("local()", 101),
+        ("{\"g\": adder(2)}.g(3)", 5),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::Int(expected));
+    }
 }
@@ -0,0 +1,157 @@
+use ns::{error::ErrorType, Alloc, HeapNode, Interpreter, Value};
+
+#[test]
+pub fn test_heap_access_live() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let heap = &mut nsi.environment_mut().heap;
+
+    let ptr = heap.allocate(HeapNode::array(vec![Value::Int(1)]));
+    assert!(
+        heap.try_access(ptr).is_ok(),
+        "Live node should be accessible"
+    );
+    assert!(
+        heap.try_access_mut(ptr).is_ok(),
+        "Live node should be accessible"
+    );
+}
+
+#[test]
+pub fn test_heap_access_freed() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let heap = &mut nsi.environment_mut().heap;
+
+    let ptr = heap.allocate(HeapNode::array(vec![]));
+    heap.deallocate(ptr);
+
+    let result = heap.try_access(ptr);
+    assert!(result.is_err(), "Freed node should not be accessible");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::InternalError);
+}
+
+#[test]
+pub fn test_heap_access_out_of_range() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let heap = &mut nsi.environment_mut().heap;
+
+    let result = heap.try_access_mut(usize::MAX);
+    assert!(
+        result.is_err(),
+        "Out of range pointer should not be accessible"
+    );
+    assert_eq!(result.unwrap_err().err_type, ErrorType::InternalError);
+}
+
+#[test]
+#[should_panic(expected = "Heap pointer does not reference a live object")]
+pub fn test_heap_access_freed_panics() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let heap = &mut nsi.environment_mut().heap;
+
+    let ptr = heap.allocate(HeapNode::object(Default::default()));
+    heap.deallocate(ptr);
+    heap.access(ptr);
+}
+
+#[test]
+pub fn test_heap_compact_releases_trailing_nodes() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    nsi.environment_mut().set_gc_enabled(false);
+
+    let state = nsi.execute_from_string(
+        "let keep = [1]; let batch = []; let i = 0;
+        while i < 5000 { batch.append([i]); i += 1; }",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+    let grown = nsi.environment().heap.capacity();
+    assert!(grown >= 5000, "Heap should grow to fit the batch");
+
+    let state = nsi.execute_from_string("batch = null;");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let released = nsi.environment_mut().compact();
+    let heap = &nsi.environment().heap;
+    assert!(released > 0, "Trailing free nodes should be released");
+    assert_eq!(heap.capacity(), grown - released);
+    assert!(
+        heap.capacity() < 5000,
+        "Heap should shrink after compaction"
+    );
+
+    let result = nsi.evaluate_from_string("keep[0] + [2, 3][1]");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(4));
+}
+
+#[test]
+pub fn test_gc_deep_chain() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let {gc} = import(\"std\");
+        let head = null;
+        let i = 0;
+        while i < 200000 { head = {\"next\": head, \"v\": i}; i += 1; }
+        gc();
+        let v = head.next.v;
+        head = null;
+        gc();",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let v = nsi.environment().get_global(&"v".to_string());
+    assert_eq!(v.unwrap(), &Value::Int(199998));
+    assert!(nsi.environment().heap.occupied() < 100);
+}
+
+#[test]
+pub fn test_heap_object_types() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let before = nsi.environment_mut().heap_object_types();
+    let live_before = nsi.environment_mut().heap_live_count();
+
+    let state = nsi.execute_from_string(
+        "let a = [[1], [2]];
+        let o = {\"x\": {}};
+        fun counter() { let c = [0]; return fun() { return c; }; }
+        let f = counter();
+        let tmp = [[3], {}];
+        tmp = null;",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let after = nsi.environment_mut().heap_object_types();
+    let added = |name| after.get(name).unwrap_or(&0) - before.get(name).unwrap_or(&0);
+    assert_eq!(added("Array"), 4);
+    assert_eq!(added("Object"), 2);
+    assert_eq!(added("Closure"), 1);
+    assert_eq!(nsi.environment_mut().heap_live_count(), live_before + 7);
+}
+
+#[test]
+pub fn test_heap_free_count() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let heap = &mut nsi.environment_mut().heap;
+
+    assert_eq!(heap.free_count(), heap.capacity() - heap.occupied());
+
+    let ptrs: Vec<usize> = (0..8)
+        .map(|i| heap.allocate(HeapNode::array(vec![Value::Int(i)])))
+        .collect();
+    let base = heap.free_count();
+    assert_eq!(base, heap.capacity() - heap.occupied());
+
+    for ptr in ptrs.iter().step_by(2) {
+        heap.deallocate(*ptr);
+    }
+    assert_eq!(heap.free_count(), base + 4);
+    assert!(
+        heap.fragmentation() > 0.0,
+        "Nodes freed between live ones should count as fragmentation"
+    );
+
+    for ptr in ptrs.iter().skip(1).step_by(2) {
+        heap.deallocate(*ptr);
+    }
+    assert_eq!(heap.free_count(), base + 8);
+}
@@ -90,3 +90,136 @@ pub fn test_if_elseif_parse_failure() {
     assert!(state.is_err(), "Statement should fail");
     assert_eq!(state.unwrap_err().err_type, ErrorType::SyntaxError);
 }
+
+#[test]
+pub fn test_empty_collections_falsy() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    for (expr, expected) in [
+        ("if [] { 1 } else { 0 }", 0),
+        ("if {} { 1 } else { 0 }", 0),
+        ("if \"\" { 1 } else { 0 }", 0),
+        ("if [0] { 1 } else { 0 }", 1),
+        ("if {\"a\": null} { 1 } else { 0 }", 1),
+        ("if ![] && ({} || [[]]) { 1 } else { 0 }", 1),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::Int(expected));
+    }
+
+    let state = nsi.execute_from_string(
+        "let ran = [];
+        if [] { ran.append(\"array\"); }
+        if {} { ran.append(\"object\"); }
+        if [1] { ran.append(\"full array\"); }
+        if {\"k\": 1} { ran.append(\"full object\"); }",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"ran".to_string()).unwrap().repr(env),
+        "['full array', 'full object']"
+    );
+
+    nsi.environment_mut().set_empty_collections_falsy(false);
+    let result = nsi.evaluate_from_string("if [] && {} { 1 } else { 0 }");
+    assert_eq!(result.unwrap(), Value::Int(1));
+}
+
+#[test]
+pub fn test_bool_hook() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "fun Stack() {
+            let self = {\"items\": []};
+            self.__bool__ = fun(s) { return s.items; };
+            return self;
+        }
+        let s = Stack();
+        let before = if s { 1 } else { 0 };
+        let negated = !s;
+        s.items.append(5);
+        let after = if s { 1 } else { 0 };
+        let {filter, len} = import(\"std\");
+        let filtered = filter([s, Stack()], fun(x) { return x; });
+        let kept = len(filtered) == 1 && filtered[0] == s;
+        fun count(st) { let n = 0; while st { st.items.pop(); n += 1; } return n; }
+        let popped = count(s);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"before".to_string()).unwrap(),
+        &Value::Int(0)
+    );
+    assert_eq!(
+        env.get_global(&"negated".to_string()).unwrap(),
+        &Value::Bool(true)
+    );
+    assert_eq!(
+        env.get_global(&"after".to_string()).unwrap(),
+        &Value::Int(1)
+    );
+    assert_eq!(
+        env.get_global(&"popped".to_string()).unwrap(),
+        &Value::Int(1)
+    );
+    assert_eq!(
+        env.get_global(&"kept".to_string()).unwrap(),
+        &Value::Bool(true)
+    );
+
+    let result =
+        nsi.evaluate_from_string("if {\"__bool__\": fun(s) { return 1 + null; }} { 1 } else { 0 }");
+    assert!(result.is_err(), "Expression should fail");
+    assert!(matches!(
+        result.unwrap_err().err_type,
+        ErrorType::TypeError(_)
+    ));
+}
+
+#[test]
+pub fn test_elif_chain() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "fun with_elif(n) {
+            if n < 0 { return \"negative\"; }
+            elif n == 0 { return \"zero\"; }
+            elif n < 10 { return \"small\"; }
+            else { return \"large\"; }
+        }
+        fun with_else_if(n) {
+            if n < 0 { return \"negative\"; }
+            else if n == 0 { return \"zero\"; }
+            else if n < 10 { return \"small\"; }
+            else { return \"large\"; }
+        }
+        fun no_else(n) {
+            let out = null;
+            if n == 1 { out = \"one\"; } elif n == 2 { out = \"two\"; }
+            return out;
+        }",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for n in [-5, 0, 3, 42] {
+        let with_elif = nsi.evaluate_from_string(&format!("with_elif({})", n));
+        let with_else_if = nsi.evaluate_from_string(&format!("with_else_if({})", n));
+        assert!(with_elif.is_ok(), "Expression should succeed");
+        assert_eq!(with_elif.unwrap(), with_else_if.unwrap());
+    }
+
+    for (n, expected) in [(1, "'one'"), (2, "'two'"), (3, "null")] {
+        let result = nsi.evaluate_from_string(&format!("no_else({})", n));
+        assert!(result.is_ok(), "Expression should succeed");
+        let env = nsi.environment();
+        assert_eq!(result.unwrap().repr(env), expected);
+    }
+
+    let state = nsi.execute_from_string("if true { 1; } elif { 2; }");
+    assert!(state.is_err(), "Statement should fail");
+}
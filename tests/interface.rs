@@ -1,6 +1,12 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
 use ns::{
     error::{Error, ErrorType},
-    Interpreter, ModuleFnRecord, NativeFnPtr, Value,
+    Alloc, HeapNode, Ins, Interpreter, ModuleExport, ModuleFnRecord, NativeFnPtr, Value,
 };
 
 #[test]
@@ -44,6 +50,26 @@ pub fn test_module_embed_failure() {
     assert_eq!(err.msg, "Can't square that");
 }
 
+#[test]
+pub fn test_module_embed_argument_count() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let count: NativeFnPtr = |_env, _arg0, argc| Ok(Value::Int(argc as i64));
+
+    nsi.environment_mut().register_module(
+        "args".to_string(),
+        vec![ModuleFnRecord::new("count".to_string(), 3, count)],
+    );
+
+    let result = nsi.evaluate_from_string("import(\"args\").count()");
+    assert!(result.is_ok(), "Evaluation should succeed");
+    assert_eq!(result.unwrap(), Value::Int(0));
+
+    let result = nsi.evaluate_from_string("import(\"args\").count(1, 2)");
+    assert!(result.is_ok(), "Evaluation should succeed");
+    assert_eq!(result.unwrap(), Value::Int(2));
+}
+
 #[test]
 pub fn test_interpreter_execute() {
     let mut nsi = Interpreter::new(false, false, vec![]);
@@ -125,3 +151,872 @@ pub fn test_stress_heap() {
 
     assert!(result.is_ok(), "Evaluation should succeed");
 }
+
+#[test]
+pub fn test_collect_inside_literal() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    // each object allocation may collect while the enclosing array is still
+    // only held by the frame's registers
+    let result = nsi.evaluate_from_string(
+        "import(\"std\").len([{}, {}, {}, {}, {}, {}, {}, {}, {}, [[], [], []]])",
+    );
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(10));
+}
+
+#[test]
+pub fn test_heap_initial_capacity() {
+    let program = "\
+        let arr = [];\
+        let std = import(\"std\");\
+        while std.len(arr) < 500 {\
+            std.append(arr, {\"n\": std.len(arr)});\
+        }\
+    ";
+
+    let mut small = Interpreter::new(false, false, vec![]);
+    assert!(small.execute_from_string(program).is_ok());
+
+    let mut large = Interpreter::with_heap_capacity(false, false, vec![], 4096);
+    assert!(large.execute_from_string(program).is_ok());
+
+    assert_eq!(large.environment().heap.capacity(), 4096);
+    assert_eq!(large.environment().heap.grow_count(), 0);
+    assert!(
+        small.environment().heap.grow_count() > large.environment().heap.grow_count(),
+        "Pre-sized heap should grow less often"
+    );
+}
+
+#[test]
+pub fn test_value_numeric_conversions() {
+    assert_eq!(Value::Int(3).as_f64(), Some(3.0));
+    assert_eq!(Value::Float(2.5).as_f64(), Some(2.5));
+    assert_eq!(Value::Int(-4).as_i64(), Some(-4));
+    assert_eq!(Value::Float(-2.9).as_i64(), Some(-2));
+    assert_eq!(Value::Float(f64::NAN).as_i64(), None);
+
+    assert!(Value::Int(0).is_number());
+    assert!(Value::Float(0.0).is_number());
+    assert!(!Value::Bool(true).is_number());
+
+    assert_eq!(Value::Null.as_f64(), None);
+    assert_eq!(Value::Bool(true).as_i64(), None);
+    assert_eq!(Value::from_string("1").as_f64(), None);
+}
+
+#[test]
+pub fn test_closure_allocation_collected() {
+    let program = "\
+        fun adder(n) { return fun(x) { return x + n; }; }\
+        let i = 0;\
+        while i < 5000 {\
+            let f = adder(i);\
+            i = f(1);\
+        }\
+    ";
+
+    let mut enabled = Interpreter::new(false, false, vec![]);
+    assert!(enabled.execute_from_string(program).is_ok());
+    assert!(
+        enabled.environment().heap.capacity() <= 64,
+        "Closure allocations should be collected"
+    );
+
+    let mut disabled = Interpreter::new(false, false, vec![]);
+    disabled.environment_mut().set_gc_enabled(false);
+    assert!(disabled.execute_from_string(program).is_ok());
+    assert!(
+        disabled.environment().heap.capacity() >= 5000,
+        "Heap should grow without automatic collection"
+    );
+}
+
+#[test]
+pub fn test_last_value() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    assert_eq!(nsi.last_value(), None);
+
+    assert!(nsi.evaluate_from_string("1 + 1").is_ok());
+    assert!(nsi.evaluate_from_string("\"a\" + \"b\"").is_ok());
+    assert_eq!(nsi.last_value(), Some(&Value::from_string("ab")));
+
+    assert!(nsi.evaluate_from_string("undefined").is_err());
+    assert_eq!(nsi.last_value(), Some(&Value::from_string("ab")));
+}
+
+#[test]
+pub fn test_outline() {
+    let result = Interpreter::outline(
+        "fun first(a) {\n    return a;\n}\n\nlet x = 1;\nfun second() {\n    return first(x);\n}\n",
+    );
+    assert!(result.is_ok(), "Source should compile");
+
+    let outline = result.unwrap();
+    assert_eq!(outline.len(), 2);
+    assert_eq!(outline[0].0, "first");
+    assert_eq!((outline[0].1.line, outline[0].1.column), (0, 0));
+    assert_eq!(outline[1].0, "second");
+    assert_eq!((outline[1].1.line, outline[1].1.column), (5, 0));
+
+    assert!(Interpreter::outline("fun broken( {").is_err());
+}
+
+#[test]
+pub fn test_outline_skips_lambdas() {
+    let result =
+        Interpreter::outline("let f = fun(a) { return a; };\nfun named() {\n    return f;\n}\n");
+    assert!(result.is_ok(), "Source should compile");
+
+    let outline = result.unwrap();
+    assert_eq!(outline.len(), 1, "Lambdas should not be listed");
+    assert_eq!(outline[0].0, "named");
+    assert_eq!((outline[0].1.line, outline[0].1.column), (1, 0));
+}
+
+#[test]
+pub fn test_tab_normalization_file_and_string() {
+    let source = "fun f() {\r\n\tlet x = 1 / 0;\r\n}\r\nf();\r\n";
+    let path = std::env::temp_dir().join("ns_test_tab_normalization.ns");
+    std::fs::write(&path, source).unwrap();
+
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let from_file = nsi.execute_from_file(path.to_str().unwrap());
+    let from_string = Interpreter::new(false, false, vec![]).execute_from_string(source);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(from_file.is_err(), "Evaluation should fail");
+    assert!(from_string.is_err(), "Evaluation should fail");
+
+    let (p0, p1) = (from_file.unwrap_err().pos, from_string.unwrap_err().pos);
+    assert_eq!(p0.map(|p| (p.line, p.column)), Some((1, 16)));
+    assert_eq!(
+        p0.map(|p| (p.line, p.column)),
+        p1.map(|p| (p.line, p.column))
+    );
+
+    nsi.environment_mut().sources.set_tab_width(2);
+    let result = nsi.execute_from_string("\tlet y = 1 / 0;");
+    assert!(result.is_err(), "Evaluation should fail");
+    assert_eq!(result.unwrap_err().pos.map(|p| p.column), Some(14));
+}
+
+#[test]
+pub fn test_native_callback_preserves_registers() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.execute_from_string(
+        "let std = import(\"std\");
+        fun pair(x) { return [x, { \"v\": x }]; }
+        let outer = [1, 2, 3];
+        let mapped = std.map(outer, fun(x) {
+            let junk = std.map([x, x, x], pair);
+            let i = 0;
+            while i < 50 { junk = pair(i); i += 1; }
+            return std.map([x], fun(y) { return pair(y)[1][\"v\"] * 10; })[0];
+        });",
+    );
+    assert!(result.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("std.str(outer)", "[1, 2, 3]"),
+        ("std.str(mapped)", "[10, 20, 30]"),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::from_string(expected));
+    }
+}
+
+#[test]
+pub fn test_nested_native_callbacks() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    // calls `f(args...)` and checks its own argument registers survive the call
+    let apply: NativeFnPtr = |env, arg0, argc| {
+        let before: Vec<Value> = (arg0..arg0 + argc).map(|i| env.reg(i).clone()).collect();
+        let result = env.call_function(&before[0], &before[1..])?;
+        let after: Vec<Value> = (arg0..arg0 + argc).map(|i| env.reg(i).clone()).collect();
+        match before == after {
+            true => Ok(result),
+            false => Error::custom_error("Arguments clobbered").err(),
+        }
+    };
+
+    nsi.environment_mut().register_module(
+        "probe".to_string(),
+        vec![ModuleFnRecord::new("apply".to_string(), 1, apply)],
+    );
+
+    let result = nsi.evaluate_from_string(
+        "import(\"probe\").apply(import(\"probe\").apply, fun(x) { return x * 2; }, 21)",
+    );
+    assert!(result.is_ok(), "Evaluation should succeed");
+    assert_eq!(result.unwrap(), Value::Int(42));
+}
+
+#[test]
+pub fn test_module_nested_exports() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let double: NativeFnPtr = |env, arg0, _argc| env.reg(arg0) + env.reg(arg0);
+
+    nsi.environment_mut().register_module(
+        "math".to_string(),
+        vec![
+            ModuleExport::Function(ModuleFnRecord::new("double".to_string(), 1, double)),
+            ModuleExport::Object(
+                "consts".to_string(),
+                vec![
+                    ModuleExport::Value("PI".to_string(), Value::Float(3.5)),
+                    ModuleExport::Object(
+                        "inner".to_string(),
+                        vec![
+                            ModuleExport::Value("value".to_string(), Value::Int(7)),
+                            ModuleExport::Function(ModuleFnRecord::new(
+                                "double".to_string(),
+                                1,
+                                double,
+                            )),
+                        ],
+                    ),
+                ],
+            ),
+        ],
+    );
+
+    for (expr, expected) in [
+        ("import(\"math\").consts.PI", Value::Float(3.5)),
+        ("import(\"math\").consts.inner.value", Value::Int(7)),
+        (
+            "import(\"math\").consts.inner.double(import(\"math\").double(3))",
+            Value::Int(12),
+        ),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Evaluation should succeed");
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    let result = nsi.execute_from_string("import(\"std\").gc();");
+    assert!(result.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("import(\"math\").consts.inner.value");
+    assert!(result.is_ok(), "Evaluation should succeed");
+    assert_eq!(result.unwrap(), Value::Int(7));
+}
+
+#[test]
+pub fn test_no_stale_register_after_execute() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let result = nsi.execute_from_string("let a = [1, 2]; let s = \"text\"; a; s + \"!\";");
+    assert!(result.is_ok(), "Statement should succeed");
+    assert_eq!(nsi.environment().reg(0), &Value::Null);
+    assert_eq!(nsi.environment().reg(1), &Value::Null);
+
+    let result = nsi.evaluate_from_string("s == \"text\"");
+    assert!(result.is_ok(), "Evaluation should succeed");
+    assert_eq!(result.unwrap(), Value::Bool(true));
+
+    let result = nsi.execute_from_string("let b = 4;");
+    assert!(result.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("_");
+    assert!(result.is_ok(), "Evaluation should succeed");
+    assert_eq!(result.unwrap(), Value::Bool(true));
+}
+
+#[test]
+pub fn test_interpreter_builder() {
+    let square: NativeFnPtr = |env, arg0, _argc| match env.reg(arg0) {
+        Value::Int(i) => Ok(Value::Int(i * i)),
+        _ => Error::custom_error("Can't square that").err(),
+    };
+
+    let mut nsi = Interpreter::builder()
+        .verbose(false)
+        .module(
+            "math",
+            vec![ModuleFnRecord::new("square".to_string(), 1, square)],
+        )
+        .global("version", Value::Int(3))
+        .build();
+
+    let state = nsi.execute_from_string("let math = import(\"math\"); let v = version + 1;");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("math.square(v) + version");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(19));
+}
+
+#[test]
+pub fn test_profile_report() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    assert!(nsi.environment().profile_report().is_empty());
+
+    nsi.environment_mut().set_profiling(true);
+    let state = nsi.execute_from_string(
+        "let i = 0;
+        while i < 5 {
+            i += 1;
+        }",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let report = nsi.environment().profile_report();
+    let count = |line| report.iter().find(|(pos, _)| pos.line == line).map(|e| e.1);
+    assert_eq!(count(0), Some(1));
+    assert_eq!(count(1), Some(6));
+    assert_eq!(count(2), Some(5));
+
+    nsi.environment_mut().set_profiling(false);
+    assert!(nsi.environment().profile_report().is_empty());
+}
+
+#[test]
+pub fn test_run_hand_built_bytecode() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let env = nsi.environment_mut();
+
+    let id = env.add_segment_with_bytecode(
+        "add".to_string(),
+        0,
+        2,
+        vec![
+            Ins::LoadK(0, 0),
+            Ins::LoadK(1, 1),
+            Ins::Add(0, 0, 1),
+            Ins::Ret(0),
+        ],
+        vec![Value::Int(2), Value::Int(3)],
+    );
+    assert!(id.is_ok(), "Segment should be accepted");
+
+    let result = env.run_segment(id.unwrap());
+    assert!(result.is_ok(), "Segment should run");
+    assert_eq!(result.unwrap(), Value::Int(5));
+
+    for bytecode in [vec![Ins::Jump(5)], vec![Ins::LoadK(0, 1)]] {
+        let id = env.add_segment_with_bytecode("bad".to_string(), 0, 1, bytecode, vec![]);
+        assert!(id.is_err(), "Segment should be rejected");
+        assert_eq!(id.unwrap_err().err_type, ErrorType::CompilerError);
+    }
+
+    let id = env.add_segment_with_bytecode(
+        "spill".to_string(),
+        0,
+        2,
+        vec![Ins::LoadN(0), Ins::Move(2, 0), Ins::Ret(0)],
+        vec![],
+    );
+    assert!(
+        id.is_err(),
+        "Register beyond the slot count should be rejected"
+    );
+    assert_eq!(id.unwrap_err().err_type, ErrorType::CompilerError);
+
+    let id = env.add_segment_with_bytecode(
+        "iter".to_string(),
+        0,
+        2,
+        vec![Ins::IterNext(0, 1), Ins::RetNone],
+        vec![],
+    );
+    assert!(
+        id.is_err(),
+        "Registers implied by an iterator should be checked"
+    );
+}
+
+#[test]
+pub fn test_module_reregistered_name() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let exports = || vec![ModuleExport::Value("n".to_string(), Value::Int(1))];
+
+    nsi.environment_mut()
+        .register_module("host".to_string(), exports());
+    let result = nsi.execute_from_string("let std = import(\"std\"); let old = import(\"host\");");
+    assert!(result.is_ok(), "Statement should succeed");
+
+    nsi.environment_mut()
+        .register_module("host".to_string(), exports());
+
+    let result = nsi.evaluate_from_string("std.str(import(\"host\"))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("<module 'host'>"));
+
+    let result = nsi.evaluate_from_string("std.typeOf(old)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("Object"),
+        "A replaced module should lose its name"
+    );
+}
+
+#[test]
+pub fn test_gc_roots_native_arguments() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    // collects while its argument is the only reference to an object, with
+    // garbage allocated in between so a freed node would be reused
+    let hold: NativeFnPtr = |env, arg0, _argc| {
+        env.gc(0, 0)?;
+        for _ in 0..100 {
+            env.heap.allocate(HeapNode::array(vec![Value::Int(0)]));
+        }
+        env.gc(0, 0)?;
+
+        match env.reg(arg0) {
+            Value::Object(p) => match env.heap.try_access(*p)? {
+                HeapNode::Object { mark: _, map } => Ok(map
+                    .get(&Value::from_string("v"))
+                    .cloned()
+                    .unwrap_or(Value::Null)),
+                _ => Error::custom_error("Object was collected").err(),
+            },
+            _ => Error::custom_error("Expected an object").err(),
+        }
+    };
+
+    nsi.environment_mut().register_module(
+        "host".to_string(),
+        vec![ModuleFnRecord::new("hold".to_string(), 1, hold)],
+    );
+
+    let result = nsi.evaluate_from_string("import(\"host\").hold({\"v\": [7]})[0]");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(7));
+
+    let result = nsi.evaluate_from_string("{\"w\": [8]}");
+    assert!(result.is_ok(), "Expression should succeed");
+    nsi.environment_mut().gc(0, 0).unwrap();
+
+    let result = nsi.evaluate_from_string("_.w[0]");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(8));
+}
+
+#[test]
+pub fn test_import_named_source() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    nsi.environment_mut().add_named_source(
+        "geometry",
+        "let PI = 3;
+        fun area(r) { return PI * r * r; }
+        fun broken() { return PI + null; }",
+    );
+
+    let state = nsi.execute_from_string(
+        "let g = import(\"geometry\");
+        let a = g.area(2);
+        let same = g == import(\"geometry\");",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(env.get_global(&"a".to_string()).unwrap(), &Value::Int(12));
+    assert_eq!(
+        env.get_global(&"same".to_string()).unwrap(),
+        &Value::Bool(true)
+    );
+
+    let result = nsi.evaluate_from_string("g.broken()");
+    assert!(result.is_err(), "Expression should fail");
+
+    let pos = result.unwrap_err().pos.unwrap();
+    let src = nsi.environment().sources.get_source(pos.src_id).unwrap();
+    assert_eq!(src.get_origin(), "geometry");
+    assert_eq!(pos.line, 2);
+
+    let result = nsi.evaluate_from_string("import(\"missing\")");
+    assert!(result.is_err(), "Expression should fail");
+}
+
+#[test]
+pub fn test_import_module_return() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let env = nsi.environment_mut();
+    env.add_named_source(
+        "config",
+        "let hidden = 1;
+        return {\"port\": 80};
+        let unreached = 2;",
+    );
+    env.add_named_source("answer", "return 42;");
+    env.add_named_source("list", "return [];");
+
+    let result = nsi.evaluate_from_string("import(\"config\").port");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(80));
+
+    let result = nsi.evaluate_from_string("import(\"config\").hidden");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::Null,
+        "Returned object should replace the declarations"
+    );
+
+    let result = nsi.evaluate_from_string("import(\"answer\")");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(42));
+
+    let result = nsi.evaluate_from_string("import(\"std\").typeOf(import(\"config\"))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("Object"),
+        "Returned objects should not be registered as modules"
+    );
+
+    let result = nsi.evaluate_from_string("import(\"std\").str(import(\"config\"))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("{ 'port': 80 }"));
+
+    let result = nsi.execute_from_string("import(\"std\").append(import(\"list\"), 1);");
+    assert!(result.is_ok(), "Statement should succeed");
+    nsi.environment_mut().gc(0, 0).unwrap();
+
+    let result = nsi.evaluate_from_string("import(\"list\")[0]");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::Int(1),
+        "Returned values should be cached rather than run again"
+    );
+}
+
+#[test]
+pub fn test_cyclic_import() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let env = nsi.environment_mut();
+    env.add_named_source("ping", "let pong = import(\"pong\");");
+    env.add_named_source("pong", "let ping = import(\"ping\");");
+    env.add_named_source("itself", "let me = import(\"itself\");");
+    env.add_named_source("leaf", "let value = 1;");
+
+    let result = nsi.evaluate_from_string("import(\"ping\")");
+    assert!(result.is_err(), "Mutual imports should fail");
+    assert!(matches!(
+        result.unwrap_err().err_type,
+        ErrorType::NameError(name) if name == "ping"
+    ));
+
+    let result = nsi.evaluate_from_string("import(\"itself\")");
+    assert!(result.is_err(), "Self import should fail");
+
+    let result = nsi.evaluate_from_string("import(\"leaf\").value");
+    assert!(result.is_ok(), "Import after a failed cycle should succeed");
+    assert_eq!(result.unwrap(), Value::Int(1));
+}
+
+#[test]
+pub fn test_locals_snapshot() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let inspect: NativeFnPtr = |env, _arg0, _argc| {
+        // the global frame is outermost, then the calling function
+        let mut locals: Vec<_> = env
+            .locals_snapshot(1)
+            .unwrap()
+            .into_iter()
+            .map(|(name, v)| format!("{}={:?}", name, v))
+            .collect();
+        locals.sort();
+        Ok(Value::from_string(&locals.join(",")))
+    };
+
+    nsi.environment_mut().register_module(
+        "debug".to_string(),
+        vec![ModuleFnRecord::new("inspect".to_string(), 0, inspect)],
+    );
+
+    let state = nsi.execute_from_string(
+        "let inspect = import(\"debug\").inspect;
+        fun f(a, b) { let c = a + b; return inspect(); }
+        let locals = f(1, 2);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+    assert_eq!(
+        nsi.environment().get_global(&"locals".to_string()).unwrap(),
+        &Value::from_string("a=Int(1),b=Int(2),c=Int(3)")
+    );
+    assert!(nsi.environment().locals_snapshot(0).is_none());
+}
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+#[test]
+pub fn test_output_writers() {
+    let (out, err) = (SharedBuffer::default(), SharedBuffer::default());
+    let mut nsi = Interpreter::builder()
+        .stdout(Box::new(out.clone()))
+        .stderr(Box::new(err.clone()))
+        .build();
+
+    let state = nsi.execute_from_string(
+        "let std = import(\"std\");
+        std.println(\"out\");
+        std.eprintln(\"warning\");
+        std.print([1, 2]);
+        std.eprint(3);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+    assert_eq!(out.contents(), "out\n[1, 2]");
+    assert_eq!(err.contents(), "warning\n3");
+
+    let redirected = SharedBuffer::default();
+    nsi.environment_mut()
+        .set_stderr(Box::new(redirected.clone()));
+    let state = nsi.execute_from_string("std.eprintln(null);");
+    assert!(state.is_ok(), "Statement should succeed");
+    assert_eq!(redirected.contents(), "null\n");
+    assert_eq!(err.contents(), "warning\n3");
+}
+
+#[test]
+pub fn test_import_relative_to_importing_file() {
+    let dir = std::env::temp_dir().join("ns_test_relative_import");
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(
+        dir.join("main.ns"),
+        "let util = import(\"./lib/util\");\nlet n = util.double(21);\nlet same = util == import(\"lib/util.ns\");",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("lib/util.ns"),
+        "let helper = import(\"helper.ns\");\nfun double(x) { return helper.twice * x; }",
+    )
+    .unwrap();
+    std::fs::write(dir.join("lib/helper.ns"), "let twice = 2;").unwrap();
+
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_file(dir.join("main.ns").to_str().unwrap());
+    let from_string = nsi.execute_from_string("import(\"./lib/util\");");
+
+    let absolute = dir.join("lib/helper.ns");
+    let state_abs = nsi.execute_from_string(&format!(
+        "let t = import({:?}).twice;",
+        absolute.to_str().unwrap()
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(state.is_ok(), "Statement should succeed");
+    let env = nsi.environment();
+    assert_eq!(env.get_global(&"n".to_string()).unwrap(), &Value::Int(42));
+    assert_eq!(
+        env.get_global(&"same".to_string()).unwrap(),
+        &Value::Bool(true)
+    );
+
+    assert!(from_string.is_err(), "Statement should fail");
+    assert!(matches!(
+        from_string.unwrap_err().err_type,
+        ErrorType::NameError(_)
+    ));
+
+    assert!(state_abs.is_ok(), "Statement should succeed");
+    assert_eq!(
+        nsi.environment().get_global(&"t".to_string()).unwrap(),
+        &Value::Int(2)
+    );
+}
+
+#[test]
+pub fn test_iterate_script_values() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let result = nsi.evaluate_from_string("[1, 2, 3].map(fun(x) { return x * x; })");
+    let ptr = match result.unwrap() {
+        Value::Array(ptr) => ptr,
+        v => panic!("expected Array, got {}", v.type_name()),
+    };
+    let values: Vec<Value> = nsi
+        .environment()
+        .iter_array(ptr)
+        .unwrap()
+        .cloned()
+        .collect();
+    assert_eq!(values, vec![Value::Int(1), Value::Int(4), Value::Int(9)]);
+
+    let result = nsi.evaluate_from_string("{\"a\": 1, \"b\": 2}");
+    let ptr = match result.unwrap() {
+        Value::Object(ptr) => ptr,
+        v => panic!("expected Object, got {}", v.type_name()),
+    };
+    let env = nsi.environment();
+    let mut pairs: Vec<(String, Value)> = env
+        .iter_object(ptr)
+        .unwrap()
+        .map(|(k, v)| (k.to_string(env), v.clone()))
+        .collect();
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        pairs,
+        vec![
+            ("a".to_string(), Value::Int(1)),
+            ("b".to_string(), Value::Int(2))
+        ]
+    );
+
+    let result = env.iter_array(ptr);
+    assert!(result.is_err(), "Object should not iterate as an array");
+    assert_eq!(
+        result.err().unwrap().err_type,
+        ErrorType::TypeError("Object")
+    );
+    assert!(env.iter_object(usize::MAX).is_err());
+}
+
+#[test]
+pub fn test_execute_from_native() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    // runs a zero-argument function as its own program while the caller's
+    // expression is still being evaluated
+    let run: NativeFnPtr = |env, arg0, _argc| match env.reg(arg0).clone() {
+        Value::Func(program, closure) => env.execute(program as usize, closure),
+        v => Error::type_error(&Value::Func(0, 0), &v).err(),
+    };
+
+    nsi.environment_mut().register_module(
+        "host".to_string(),
+        vec![ModuleFnRecord::new("run".to_string(), 1, run)],
+    );
+
+    let state = nsi.execute_from_string(
+        "let host = import(\"host\");
+        let g = 5;
+        let base = 100;
+        fun compute(n) {
+            let k = 3;
+            return k + n * host.run(fun() { let a = [1, 2]; return a[1] * base; }) + g;
+        }
+        let x = 1 + host.run(fun() { return g * 10; }) * 2 + g;
+        let y = compute(2);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(env.get_global(&"x".to_string()).unwrap(), &Value::Int(106));
+    assert_eq!(env.get_global(&"y".to_string()).unwrap(), &Value::Int(408));
+    assert_eq!(env.get_global(&"g".to_string()).unwrap(), &Value::Int(5));
+}
+
+#[test]
+pub fn test_small_int_literals_load_inline() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string("let x = 5;");
+    assert!(state.is_ok(), "Statement should succeed");
+    assert_eq!(
+        nsi.environment().get_global(&"x".to_string()).unwrap(),
+        &Value::Int(5)
+    );
+
+    let main = nsi.environment().get_segment(0);
+    assert!(main
+        .bytecode()
+        .iter()
+        .any(|ins| matches!(ins, Ins::LoadI(_, 5))));
+    assert!(!main.consts().contains(&Value::Int(5)));
+
+    let state = nsi.execute_from_string("let y = 100000; let z = -32768;");
+    assert!(state.is_ok(), "Statement should succeed");
+    assert_eq!(
+        nsi.environment().get_global(&"y".to_string()).unwrap(),
+        &Value::Int(100000)
+    );
+    assert_eq!(
+        nsi.environment().get_global(&"z".to_string()).unwrap(),
+        &Value::Int(-32768)
+    );
+
+    let main = nsi.environment().get_segment(0);
+    let k = main
+        .consts()
+        .iter()
+        .position(|v| v == &Value::Int(100000))
+        .expect("Large integers should be pooled");
+    assert!(main
+        .bytecode()
+        .iter()
+        .any(|ins| matches!(ins, Ins::LoadK(_, i) if *i as usize == k)));
+}
+
+#[test]
+pub fn test_repl_evaluates_negative_numbers() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let mut output = Vec::new();
+
+    nsi.repl_with(
+        io::Cursor::new("=-5\n= -5\n=-2.5\n=-x\nlet x = 3;\n=-x\n=--x\n"),
+        &mut output,
+    );
+
+    let output = String::from_utf8(output).unwrap();
+    let results: Vec<&str> = output
+        .split(">> ")
+        .skip(1)
+        .filter_map(|s| s.strip_suffix('\n'))
+        .collect();
+    assert_eq!(results, vec!["-5", "-5", "-2.5", "-3", "3"]);
+    assert_eq!(nsi.last_value(), Some(&Value::Int(3)));
+}
+
+#[test]
+pub fn test_discover_global_functions() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let ran = [];
+        fun test_add() { ran.append(\"add\"); return 1 + 1; }
+        fun test_concat() { ran.append(\"concat\"); return \"a\" + \"b\"; }
+        fun helper() { return null; }
+        let not_a_function = 5;
+        for i in 0..1 { }",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let functions = nsi.environment().global_functions();
+    let names: Vec<&str> = functions.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["helper", "test_add", "test_concat"]);
+
+    let mut results = vec![];
+    for (name, f) in functions
+        .iter()
+        .filter(|(name, _)| name.starts_with("test_"))
+    {
+        let result = nsi.environment_mut().call_function(f, &[]);
+        assert!(result.is_ok(), "{} should succeed", name);
+        results.push(result.unwrap());
+    }
+    assert_eq!(results, vec![Value::Int(2), Value::from_string("ab")]);
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"ran".to_string()).unwrap().repr(env),
+        "['add', 'concat']"
+    );
+}
@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use ns::{error::ErrorType, Interpreter, Value};
+use ns::{error::ErrorType, Interpreter, Pos, Value};
 
 #[test]
 pub fn test_comment() {
@@ -73,3 +73,240 @@ pub fn test_invalid_import() {
         ErrorType::NameError("math".to_string())
     );
 }
+
+#[test]
+pub fn test_invalid_binary_operator() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("1 ! 2");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::CompilerError);
+}
+
+#[test]
+pub fn test_assignment_in_expression() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string("let y = 0; let x = y = 2;");
+    assert!(state.is_err(), "Statement should fail");
+    assert_eq!(state.unwrap_err().err_type, ErrorType::SyntaxError);
+}
+
+#[test]
+pub fn test_extended_escape_chars() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("\"\\0 \\b \\f\"");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::String(Rc::new("\0 \x08 \x0c".to_string()))
+    );
+}
+
+#[test]
+pub fn test_hex_escape_chars() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("\"\\x41\\x7a\\xff\"");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::String(Rc::new("Az\u{ff}".to_string()))
+    );
+}
+
+#[test]
+pub fn test_invalid_hex_escape_char() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("\"\\xZZ\"");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::SyntaxError);
+
+    let result = nsi.evaluate_from_string("\"\\x4\"");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::SyntaxError);
+}
+
+#[test]
+pub fn test_unexpected_token_span() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.execute_from_string("let x = 5 while;");
+    assert!(result.is_err(), "Expression should fail");
+
+    let err = result.unwrap_err();
+    assert_eq!(err.err_type, ErrorType::SyntaxError);
+    assert_eq!(err.code(), ErrorType::SyntaxError.code());
+
+    let (start, end) = (err.pos.unwrap(), err.span_end.unwrap());
+    assert_eq!(start.column, 10);
+    assert_eq!(end.column, 14);
+    assert_eq!(start.line, end.line);
+
+    let err = err.with_pos(Some(&start));
+    assert_eq!(
+        err.span_end,
+        Some(end),
+        "Span should survive a position in the same source"
+    );
+
+    let elsewhere = Pos {
+        src_id: start.src_id + 1,
+        ..start
+    };
+    let err = err.with_pos(Some(&elsewhere));
+    assert_eq!(
+        err.span_end, None,
+        "Span should be dropped for a position in another source"
+    );
+}
+
+#[test]
+pub fn test_expression_statements() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string("1 + 1; \"text\"; (fun(x) { return x; })(3); -2;");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let state =
+        nsi.execute_from_string("let std = import(\"std\"); std.print(3); std.str(4) + \"!\";");
+    assert!(state.is_ok(), "Statement should succeed");
+}
+
+#[test]
+pub fn test_assignment_statements() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string(
+        "let a = [1, 2]; let o = {}; let y = 3; y = y * 2 + 1; a[0] = y; o.n = 5 - 3;",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("a[0] * 10 + y + o.n");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(79));
+
+    let state = nsi.execute_from_string("fun f(x) { return x; } f(a = 5);");
+    assert!(state.is_err(), "Assignment as an argument should fail");
+    assert_eq!(state.unwrap_err().err_type, ErrorType::SyntaxError);
+
+    let state = nsi.execute_from_string("a = y = 5;");
+    assert!(state.is_err(), "Chained assignment should fail");
+    assert_eq!(state.unwrap_err().err_type, ErrorType::SyntaxError);
+
+    let state = nsi.execute_from_string("y + 1 = 5;");
+    assert!(state.is_err(), "Assignment to an expression should fail");
+    assert_eq!(state.unwrap_err().err_type, ErrorType::SyntaxError);
+}
+
+#[test]
+pub fn test_incomplete_input_errors() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for src in [
+        "fun f() {",
+        "let x = [1, 2",
+        "let o = {\"a\": 1,",
+        "let y = a.",
+    ] {
+        let err = nsi.execute_from_string(src);
+        assert!(err.is_err(), "Statement should fail");
+        assert!(
+            err.unwrap_err().incomplete,
+            "Error should be incomplete: {}",
+            src
+        );
+    }
+
+    for src in ["let 5 = 1;", "let x = );", "let z = 1 + * 2;"] {
+        let err = nsi.execute_from_string(src);
+        assert!(err.is_err(), "Statement should fail");
+        assert!(
+            !err.unwrap_err().incomplete,
+            "Error should not be incomplete: {}",
+            src
+        );
+    }
+}
+
+#[test]
+pub fn test_let_without_initializer() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let x;
+        let before = x;
+        x = 5;
+        fun f(flag) { let y; if flag { y = 1; } return y; }
+        let a = f(true);
+        let b = f(false);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(env.get_global(&"before".to_string()).unwrap(), &Value::Null);
+    assert_eq!(env.get_global(&"x".to_string()).unwrap(), &Value::Int(5));
+    assert_eq!(env.get_global(&"a".to_string()).unwrap(), &Value::Int(1));
+    assert_eq!(env.get_global(&"b".to_string()).unwrap(), &Value::Null);
+}
+
+#[test]
+pub fn test_evaluation_error_positions() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let err = nsi.evaluate_from_string("1 + * 2").unwrap_err();
+    assert_eq!(err.err_type, ErrorType::SyntaxError);
+    assert_eq!(err.pos.unwrap().column, 4);
+
+    let err = nsi.evaluate_from_string("1 +").unwrap_err();
+    assert!(err.incomplete, "Expression should fail as incomplete");
+    assert_eq!(err.pos.unwrap().line, 0);
+
+    let result = nsi.evaluate_from_string("1 + 2;");
+    assert_eq!(result.unwrap(), Value::Int(3));
+
+    let result = nsi.evaluate_from_string("1 + 2; 3");
+    assert!(result.is_err(), "Expression should fail");
+}
+
+#[test]
+pub fn test_comments_inside_expressions() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "fun add(
+            a, # first
+            # own line
+            b
+        ) { return a + b; }
+        let arr = [ # open
+            1, # one
+            # own line
+            2
+            # before close
+        ];
+        let obj = {
+            # leading
+            \"x\": 1, # trailing
+            # between
+            \"y\": # after colon
+                2
+        };
+        let sum = add(
+            # leading
+            arr[0], # trailing
+            # between
+            obj.y
+            # before close
+        );
+        {
+            # leading
+            \"z\" # after key
+            : 3
+        }.z;",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"arr".to_string()).unwrap().repr(env),
+        "[1, 2]"
+    );
+    assert_eq!(env.get_global(&"sum".to_string()).unwrap(), &Value::Int(3));
+
+    let result = nsi.evaluate_from_string("obj.x + # mid\n obj.y");
+    assert_eq!(result.unwrap(), Value::Int(3));
+}
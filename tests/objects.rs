@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use ns::{error::ErrorType, Alloc, HeapNode, Interpreter, Value};
 
 #[test]
@@ -69,6 +71,27 @@ pub fn test_array_subscript_assign_invalid_index() {
     assert_eq!(state.unwrap_err().err_type, ErrorType::IndexError(5));
 }
 
+#[test]
+pub fn test_array_subscript_assign_append() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string("let arr = [1, 2, 3]; arr[3] = 4;");
+    assert!(state.is_err(), "Statement should fail");
+    assert_eq!(state.unwrap_err().err_type, ErrorType::IndexError(3));
+
+    nsi.environment_mut().set_append_on_assign(true);
+    let state =
+        nsi.execute_from_string("arr[3] = 4; arr[import(\"std\").len(arr)] = 5; _ = arr[4];");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(5));
+
+    let state = nsi.execute_from_string("arr[7] = 0;");
+    assert!(state.is_err(), "Statement should fail");
+    assert_eq!(state.unwrap_err().err_type, ErrorType::IndexError(7));
+}
+
 #[test]
 pub fn test_array_subscript_assign_invalid_type() {
     let mut nsi = Interpreter::new(false, false, vec![]);
@@ -178,3 +201,272 @@ pub fn test_gc() {
     let state = nsi.execute_from_string("_ = {\"a\": 3}.a; import(\"std\").gc();");
     assert!(state.is_ok(), "Statement should succeed");
 }
+
+#[test]
+pub fn test_array_spread() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let a = [1, 2]; let b = [5]; _ = import(\"std\").str([...a, 3, 4, ...b, ...[]]);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(
+        val.unwrap(),
+        &Value::String(Rc::new("[1, 2, 3, 4, 5]".to_string()))
+    );
+}
+
+#[test]
+pub fn test_object_spread_override() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let base = {\"x\": 0, \"y\": 2}; let obj = {...base, \"x\": 1}; _ = obj.x + obj.y;",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(3));
+
+    let result = nsi.evaluate_from_string("base.x");
+    assert_eq!(result.unwrap(), Value::Int(0), "Source object is unchanged");
+}
+
+#[test]
+pub fn test_spread_type_mismatch() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let result = nsi.evaluate_from_string("[...{\"a\": 1}]");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Object"));
+
+    let result = nsi.evaluate_from_string("{...[1]}");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Array"));
+}
+
+#[test]
+pub fn test_object_mixed_key_repr() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").str({1: \"a\", true: \"b\"})");
+    assert!(result.is_ok(), "Expression should succeed");
+
+    let s = match result.unwrap() {
+        Value::String(s) => s.to_string(),
+        v => panic!("Expected string, recieved {:?}", v),
+    };
+    assert!(s.contains("1: 'a'"), "Int key should render unquoted");
+    assert!(s.contains("true: 'b'"), "Bool key should render unquoted");
+
+    let result = nsi.evaluate_from_string("import(\"std\").str([{2.0: 1}, {2: 1}, {\"2\": 1}])");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::String(Rc::new("[{ 2.0: 1 }, { 2: 1 }, { '2': 1 }]".to_string()))
+    );
+
+    let result = nsi.evaluate_from_string("import(\"std\").str([2.0, {\"x\": 3.0}, 2.5])");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("[2.0, { 'x': 3.0 }, 2.5]"),
+        "Whole float elements and values should keep their fraction"
+    );
+
+    let result = nsi.evaluate_from_string("import(\"std\").repr(2.0)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("2.0"));
+
+    let result = nsi.evaluate_from_string("import(\"std\").str(2.0)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("2"),
+        "A float on its own should display as before"
+    );
+}
+
+#[test]
+pub fn test_object_literal_statement() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "{ \"a\": 1 };
+        { ...{ \"b\": 2 }, \"c\": 3 };
+        let x = { \"k\": 4 }.k;",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+    assert_eq!(
+        nsi.environment().get_global(&"x".to_string()).unwrap(),
+        &Value::Int(4)
+    );
+}
+
+#[test]
+pub fn test_bare_block_statement() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let x = 1;
+        { let y = 2; x = x + y; }
+        {}
+        fun f() { { return 5; } }
+        let z = f();",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(env.get_global(&"x".to_string()).unwrap(), &Value::Int(3));
+    assert_eq!(env.get_global(&"z".to_string()).unwrap(), &Value::Int(5));
+}
+
+#[test]
+pub fn test_object_duplicate_literal_keys() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    for expr in [
+        "{\"a\": 1, \"b\": 2, \"a\": 3}",
+        "{1: true, 1: false}",
+        "{null: 1, \"x\": 2, null: 3}",
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+        assert_eq!(result.unwrap_err().err_type, ErrorType::CompilerError);
+    }
+
+    for expr in [
+        "{\"a\": 1, \"b\": 2, 1: 3, 1.0: 4, true: 5}",
+        "{\"a\": 1, ...{\"a\": 2}}",
+        "{\"a\": 1, \"\" + \"a\": 2}",
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+    }
+}
+
+#[test]
+pub fn test_compound_subscript_assign() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string("let arr = [1, 2]; arr[0] += 10; _ = arr[0];");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(11));
+
+    let state = nsi.execute_from_string("let grid = [[5]]; grid[0][0] -= 2; _ = grid[0][0];");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(3));
+}
+
+#[test]
+pub fn test_compound_member_assign() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string("let obj = {\"x\": 2}; obj.x *= 3; _ = obj.x;");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(6));
+
+    let state = nsi.execute_from_string(
+        "fun f() { let o = {\"n\": [1]}; o.n[0] += 1; return o.n[0]; } _ = f();",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"_".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(2));
+}
+
+#[test]
+pub fn test_array_slice() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string("let a = [1, 2, 3, 4]; let b = a[:]; b[0] = 9;");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("a[1:3]", "[2, 3]"),
+        ("a[:2]", "[1, 2]"),
+        ("a[2:]", "[3, 4]"),
+        ("a[-5:10]", "[1, 2, 3, 4]"),
+        ("a[3:1]", "[]"),
+        ("b", "[9, 2, 3, 4]"),
+        ("a", "[1, 2, 3, 4]"),
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").str({})", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(
+            result.unwrap(),
+            Value::String(Rc::new(expected.to_string()))
+        );
+    }
+
+    for expr in ["a[\"x\":]", "5[1:2]", "{}[:]"] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+        assert!(matches!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(_)
+        ));
+    }
+}
+
+#[test]
+pub fn test_string_slice() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    for (expr, expected) in [
+        ("\"hello\"[1:3]", "el"),
+        ("\"hello\"[:2]", "he"),
+        ("\"hello\"[3:]", "lo"),
+        ("\"hello\"[-2:99]", "hello"),
+        ("\"hello\"[4:1]", ""),
+        ("\"héllo wörld\"[1:4]", "éll"),
+        ("\"héllo wörld\"[7:]", "örld"),
+        ("\"日本語\"[1:2]", "本"),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(
+            result.unwrap(),
+            Value::String(Rc::new(expected.to_string()))
+        );
+    }
+}
+
+#[test]
+pub fn test_object_keyword_and_quoted_keys() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let obj = {\"has space\": 1, return: 2, if: 3, in: 4};
+        obj.while = 5;
+        { fun: 6 }.fun;",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("obj[\"has space\"]", 1),
+        ("obj[\"return\"]", 2),
+        ("obj.return", 2),
+        ("obj.if", 3),
+        ("obj[\"in\"]", 4),
+        ("obj[\"while\"]", 5),
+        ("{...obj, for: 6}.for", 6),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::Int(expected));
+    }
+
+    let result = nsi.evaluate_from_string("{if true { \"k\" } else { \"j\" }: 1}.k");
+    assert_eq!(result.unwrap(), Value::Int(1));
+
+    let result = nsi.evaluate_from_string("{return 1}");
+    assert!(result.is_err(), "Expression should fail");
+}
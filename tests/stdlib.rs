@@ -62,6 +62,19 @@ pub fn test_std_len_fail() {
     let result = nsi.evaluate_from_string("import(\"std\").len(null)");
     assert!(result.is_err(), "Statement should fail");
     assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Null"));
+
+    for (expr, type_name) in [
+        ("import(\"std\").len(5)", "Int"),
+        ("import(\"std\").len(true)", "Boolean"),
+        ("import(\"std\").len(fun() {})", "Function"),
+    ] {
+        let err = nsi.evaluate_from_string(expr).unwrap_err();
+        assert_eq!(err.err_type, ErrorType::TypeError(type_name));
+        assert_eq!(
+            err.msg,
+            format!("Value of type {} has no length", type_name)
+        );
+    }
 }
 
 #[test]
@@ -280,3 +293,1305 @@ pub fn test_std_parse_float_err() {
     assert!(result.is_err(), "Expression should fail");
     assert_eq!(result.unwrap_err().err_type, ErrorType::ValueError);
 }
+
+#[test]
+pub fn test_std_append_returns_array() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result =
+        nsi.evaluate_from_string("import(\"std\").len(import(\"std\").append([1], 2)) == 2");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Bool(true));
+}
+
+#[test]
+pub fn test_std_append_chained() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string(
+        "import(\"std\").str(import(\"std\").append(import(\"std\").append([1], 2), 3))",
+    );
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::String(Rc::new("[1, 2, 3]".to_string()))
+    );
+}
+
+#[test]
+pub fn test_std_take_present_null() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").take({\"a\": null}, \"a\")");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Null);
+}
+
+#[test]
+pub fn test_std_take_missing_key() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").take({\"a\": null}, \"b\")");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(
+        result.unwrap_err().err_type,
+        ErrorType::KeyError(Value::String(Rc::new("b".to_string())))
+    );
+
+    let result = nsi.evaluate_from_string("import(\"std\").remove({\"a\": null}, \"b\")");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Null);
+}
+
+#[test]
+pub fn test_std_take_invalid_type() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").take([1], 0)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Array"));
+}
+
+#[test]
+pub fn test_std_destructured_import() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.execute_from_string("let {str, len} = import(\"std\");");
+    assert!(result.is_ok(), "Expression should succeed");
+
+    let result = nsi.evaluate_from_string("str(len([1, 2, 3]))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::String(Rc::new("3".to_string())));
+
+    let result = nsi.execute_from_string(
+        "fun f() { let {len} = import(\"std\"); return len(\"ab\"); } _ = f();",
+    );
+    assert!(result.is_ok(), "Expression should succeed");
+}
+
+#[test]
+pub fn test_std_destructured_import_missing() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.execute_from_string("let {len, missing} = import(\"std\");");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(
+        result.unwrap_err().err_type,
+        ErrorType::KeyError(Value::String(Rc::new("missing".to_string())))
+    );
+}
+
+#[test]
+pub fn test_std_keys_stable_order() {
+    let program = "import(\"std\").str(import(\"std\").keys({\"a\": 1, \"b\": 2, \"c\": 3, 4: 4, 5.5: 5, true: 6}))";
+
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let first = nsi.evaluate_from_string(program);
+    assert!(first.is_ok(), "Expression should succeed");
+
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let second = nsi.evaluate_from_string(program);
+    assert!(second.is_ok(), "Expression should succeed");
+
+    assert_eq!(first.unwrap(), second.unwrap());
+}
+
+#[test]
+pub fn test_std_floor_mod() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").floorMod(-10, 3)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(2));
+
+    let result = nsi.evaluate_from_string("import(\"std\").floorMod(10, -3)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(-2));
+
+    let result = nsi.evaluate_from_string("import(\"std\").floorMod(-7.5, 2)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Float(0.5));
+
+    let result = nsi.evaluate_from_string("-10 % 3");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(-1));
+}
+
+#[test]
+pub fn test_std_floor_mod_zero_division() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").floorMod(10, 0)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(
+        result.unwrap_err().err_type,
+        ErrorType::ArithmeticError(Value::Int(0))
+    );
+}
+
+#[test]
+pub fn test_std_arity() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.execute_from_string("fun f(a, b) { return a + b; }");
+    assert!(result.is_ok(), "Expression should succeed");
+
+    let result = nsi.evaluate_from_string("import(\"std\").arity(f)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(2));
+
+    let result = nsi.evaluate_from_string("import(\"std\").arity(fun() { return 1; })");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(0));
+
+    let result = nsi.evaluate_from_string("import(\"std\").arity(import(\"std\").insert)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(3));
+}
+
+#[test]
+pub fn test_std_arity_invalid_type() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").arity(5)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Int"));
+}
+
+#[test]
+pub fn test_std_repr() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").repr(\"hi\")");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::String(Rc::new("'hi'".to_string())));
+
+    let result = nsi.evaluate_from_string("import(\"std\").str(\"hi\")");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::String(Rc::new("hi".to_string())));
+
+    let result = nsi.evaluate_from_string("import(\"std\").repr([\"a\", 1])");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::String(Rc::new("['a', 1]".to_string()))
+    );
+}
+
+#[test]
+pub fn test_std_is_empty() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for (expr, expected) in [
+        ("[]", true),
+        ("[1]", false),
+        ("{}", true),
+        ("{\"a\": 1}", false),
+        ("\"\"", true),
+        ("\"abc\"", false),
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").isEmpty({})", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::Bool(expected));
+    }
+}
+
+#[test]
+pub fn test_std_is_empty_queue() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.execute_from_string(
+        "let std = import(\"std\"); let q = [1, 2, 3]; let total = 0; while !std.isEmpty(q) { total += std.pop(q); }",
+    );
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        nsi.environment().get_global(&"total".to_string()),
+        Some(&Value::Int(6))
+    );
+}
+
+#[test]
+pub fn test_std_is_empty_invalid_type() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").isEmpty(5)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Int"));
+}
+
+#[test]
+pub fn test_std_parse_int_prefixes() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for (expr, expected) in [
+        ("\"0b101\"", 5),
+        ("\"0xff\"", 255),
+        ("\"0o17\"", 15),
+        ("\"-0x10\"", -16),
+        ("\"1_000\"", 1000),
+        ("\"ff\", 16", 255),
+        ("\"0xff\", 16", 255),
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").parseInt({})", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::Int(expected));
+    }
+}
+
+#[test]
+pub fn test_std_parse_int_prefix_conflict() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for expr in ["\"0b101\", 16", "\"1__0\"", "\"_1\"", "\"0x\"", "\"10\", 1"] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").parseInt({})", expr));
+        assert!(result.is_err(), "Expression should fail");
+        assert_eq!(result.unwrap_err().err_type, ErrorType::ValueError);
+    }
+}
+
+#[test]
+pub fn test_std_parse_trims_whitespace() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for (expr, expected) in [
+        ("parseInt(\" 42 \")", Value::Int(42)),
+        ("parseInt(\"\\t-0x1f\\n\")", Value::Int(-31)),
+        ("parseInt(\" ff\", 16)", Value::Int(255)),
+        ("parseFloat(\"  2.5\\r\\n\")", Value::Float(2.5)),
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    for expr in [
+        "parseInt(\"4 2\")",
+        "parseInt(\"- 4\")",
+        "parseInt(\" \")",
+        "parseFloat(\"1. 5\")",
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_err(), "Expression should fail");
+        assert_eq!(result.unwrap_err().err_type, ErrorType::ValueError);
+    }
+}
+
+#[test]
+pub fn test_std_string_casing() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for (expr, expected) in [
+        ("toUpper(\"hello\")", "HELLO"),
+        ("toLower(\"HeLLo\")", "hello"),
+        ("capitalize(\"hello world\")", "Hello world"),
+        ("capitalize(\"\")", ""),
+        ("title(\"hello world\")", "Hello World"),
+        ("title(\"élan  vital\")", "Élan  Vital"),
+        ("toUpper(\"straße\")", "STRASSE"),
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(
+            result.unwrap(),
+            Value::String(Rc::new(expected.to_string()))
+        );
+    }
+}
+
+#[test]
+pub fn test_std_string_casing_invalid_type() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").title(5)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Int"));
+}
+
+#[test]
+pub fn test_std_flatten() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi
+        .evaluate_from_string("import(\"std\").str(import(\"std\").flatten([[1, 2], [3], [4]]))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::String(Rc::new("[1, 2, 3, 4]".to_string()))
+    );
+
+    let result =
+        nsi.evaluate_from_string("import(\"std\").str(import(\"std\").flatten([1, [[2]], []]))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::String(Rc::new("[1, [2]]".to_string()))
+    );
+
+    let result = nsi.evaluate_from_string("import(\"std\").flatten(5)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Int"));
+}
+
+#[test]
+pub fn test_std_flat_map() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.execute_from_string(
+        "let std = import(\"std\");
+        let pairs = std.flatMap([1, 2, 3], fun(x) { return [x, x * 10]; });
+        let nested = std.flatMap([1, 2], fun(x) { return std.flatMap([x], fun(y) { return [y, -y]; }); });
+        let scalars = std.flatMap([1, 2], fun(x) { std.gc(); return x + 1; });",
+    );
+    assert!(result.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("std.str(pairs)", "[1, 10, 2, 20, 3, 30]"),
+        ("std.str(nested)", "[1, -1, 2, -2]"),
+        ("std.str(scalars)", "[2, 3]"),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(
+            result.unwrap(),
+            Value::String(Rc::new(expected.to_string()))
+        );
+    }
+}
+
+#[test]
+pub fn test_std_flat_map_callback_error() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result =
+        nsi.evaluate_from_string("import(\"std\").flatMap([1, 0], fun(x) { return 1 / x; })");
+    assert!(result.is_err(), "Expression should fail");
+
+    let result = nsi.evaluate_from_string("import(\"std\").flatMap([1], 3)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Int"));
+
+    let result = nsi.evaluate_from_string("1 + 2");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(3));
+}
+
+#[test]
+pub fn test_std_filter_hook_collects() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    // the callback drops the element from the input array, so only `filter`
+    // holds it while the `__bool__` hook collects and allocates over it
+    let result = nsi.execute_from_string(
+        "let std = import(\"std\");\
+        let arr = [{\"v\": 7}];\
+        let kept = std.filter(arr, fun(x) {\
+            std.pop(arr);\
+            return {\"__bool__\": fun(s) {\
+                std.gc();\
+                let junk = [{\"v\": 0}, {\"v\": 0}, {\"v\": 0}, {\"v\": 0}];\
+                return true;\
+            }};\
+        });",
+    );
+    assert!(result.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("kept[0].v");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(7));
+}
+
+#[test]
+pub fn test_std_min_max() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for (expr, expected) in [
+        ("max([3, 1, 2])", Value::Int(3)),
+        ("min([3, 1, 2])", Value::Int(1)),
+        ("max(3, 7, 2)", Value::Int(7)),
+        ("min(4.5, 1.5)", Value::Float(1.5)),
+        ("max(\"a\", \"c\", \"b\")", Value::from_string("c")),
+        ("min(5)", Value::Int(5)),
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    let result = nsi.evaluate_from_string("import(\"std\").max(1, 2.5)");
+    assert!(result.is_ok(), "Mixed Int and Float should succeed");
+    assert_eq!(result.unwrap(), Value::Float(2.5));
+
+    let result = nsi.evaluate_from_string("import(\"std\").min([3, 1.5, 2])");
+    assert!(result.is_ok(), "Mixed Int and Float should succeed");
+    assert_eq!(result.unwrap(), Value::Float(1.5));
+
+    let result = nsi.evaluate_from_string("import(\"std\").max(2, 2.0)");
+    assert!(result.is_ok(), "Equal Int and Float should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::Int(2),
+        "The first of equal values should be kept"
+    );
+}
+
+#[test]
+pub fn test_std_min_max_invalid() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").min([])");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::IndexError(0));
+
+    for expr in ["max([1, \"a\"])", "min(\"b\", 2.0)", "max(null, 1)"] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_err(), "Expression should fail");
+        assert!(matches!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(_)
+        ));
+    }
+}
+
+#[test]
+pub fn test_assertions() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for expr in [
+        "assertEq(1, 1)",
+        "assertEq([1, { \"a\": [2] }], [1, { \"a\": [2] }])",
+        "assertNeq([1], [1, 2])",
+        "assertTrue(1 < 2)",
+        "assertFalse(null)",
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"test\").{}", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+    }
+
+    let result = nsi.evaluate_from_string("import(\"test\").assertEq([1], [2])");
+    assert!(result.is_err(), "Expression should fail");
+    let e = result.unwrap_err();
+    assert_eq!(e.err_type, ErrorType::CustomError);
+    assert!(e.msg.contains("[1]") && e.msg.contains("[2]"));
+
+    let result = nsi.evaluate_from_string("import(\"test\").assertTrue(\"\")");
+    assert!(result.is_err(), "Expression should fail");
+    assert!(result.unwrap_err().msg.contains("''"));
+}
+
+#[test]
+pub fn test_std_number_formatting() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for (expr, expected) in [
+        ("toFixed(3.14159, 2)", "3.14"),
+        ("toFixed(2, 3)", "2.000"),
+        ("toFixed(2.5, 0)", "2"),
+        ("toHex(255)", "ff"),
+        ("toHex(-255)", "-ff"),
+        ("toBin(5)", "101"),
+        ("toBin(0)", "0"),
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::from_string(expected));
+    }
+
+    let result =
+        nsi.evaluate_from_string("import(\"std\").parseInt(\"0x\" + import(\"std\").toHex(4096))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Int(4096));
+}
+
+#[test]
+pub fn test_std_number_formatting_invalid() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.evaluate_from_string("import(\"std\").toFixed(1.5, -1)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::ValueError);
+
+    for expr in ["toFixed(\"1\", 2)", "toHex(1.5)", "toBin(null)"] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_err(), "Expression should fail");
+        assert!(matches!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(_)
+        ));
+    }
+}
+
+#[test]
+pub fn test_std_split() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for (expr, expected) in [
+        ("splitWhitespace(\"  a   b \")", "['a', 'b']"),
+        ("splitWhitespace(\"\\t\\n \")", "[]"),
+        ("lines(\"x\\ny\")", "['x', 'y']"),
+        ("lines(\"x\\r\\ny\\n\")", "['x', 'y']"),
+        ("split(\"a,b,,c\", \",\")", "['a', 'b', '', 'c']"),
+        ("split(\"abc\", \"\")", "['a', 'b', 'c']"),
+    ] {
+        let result =
+            nsi.evaluate_from_string(&format!("import(\"std\").repr(import(\"std\").{})", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::from_string(expected));
+    }
+
+    for expr in [
+        "splitWhitespace(1)",
+        "lines(null)",
+        "split(\"a\", 1)",
+        "split([], \",\")",
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_err(), "Expression should fail");
+        assert!(matches!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(_)
+        ));
+    }
+}
+
+#[test]
+pub fn test_std_split_limit() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string("let {split, repr} = import(\"std\");");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("repr(split(\"a,b,c\", \",\", 2))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("['a', 'b,c']"),
+        "Last part should keep the remaining separators"
+    );
+
+    let result = nsi.evaluate_from_string("repr(split(\"a,b,c\", \",\", 1))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("['a,b,c']"));
+
+    let result = nsi.evaluate_from_string("repr(split(\"a,b,c\", \",\", 5))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("['a', 'b', 'c']"),
+        "Limit above the part count should split fully"
+    );
+
+    let result = nsi.evaluate_from_string("repr(split(\"a,b,c\", \",\", 0))");
+    assert!(result.is_ok(), "Zero limit should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("[]"),
+        "Zero limit should give no parts"
+    );
+
+    let result = nsi.evaluate_from_string("repr(split(\"a,b,c\", \",\", -1))");
+    assert!(result.is_ok(), "Negative limit should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("['a', 'b', 'c']"),
+        "Negative limit should split fully"
+    );
+
+    let result = nsi.evaluate_from_string("repr(split(\"abc\", \"\", 2))");
+    assert!(result.is_ok(), "Empty separator should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("['a', 'bc']"));
+
+    let result = nsi.evaluate_from_string("repr(split(\"abc\", \"\", 0))");
+    assert!(result.is_ok(), "Empty separator should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("[]"));
+
+    let result = nsi.evaluate_from_string("\"a b c\".split(\" \", 2)");
+    assert!(result.is_ok(), "Expression should succeed");
+
+    let result = nsi.evaluate_from_string("import(\"std\").split(\"a,b\", \",\", \"2\")");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("String"));
+
+    let result = nsi.evaluate_from_string("import(\"std\").split(\"a\", \",\", 1, 2)");
+    assert!(result.is_err(), "Expression should fail");
+    assert!(matches!(
+        result.unwrap_err().err_type,
+        ErrorType::ArgumentError(_, _)
+    ));
+}
+
+#[test]
+pub fn test_std_module_type_and_repr() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for (expr, expected) in [
+        ("typeOf(import(\"std\"))", "Module"),
+        ("typeOf(import(\"test\"))", "Module"),
+        ("typeOf({})", "Object"),
+        ("str(import(\"std\"))", "<module 'std'>"),
+        ("repr([import(\"test\")])", "[<module 'test'>]"),
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::from_string(expected));
+    }
+}
+
+#[test]
+pub fn test_std_summary() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.execute_from_string(
+        "let std = import(\"std\"); let big = []; let i = 0; while i < 1000 { std.append(big, i); i += 1; }",
+    );
+    assert!(result.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        (
+            "std.summary(big)",
+            "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, ... (+990 more)]",
+        ),
+        ("std.summary(big, 2)", "[0, 1, ... (+998 more)]"),
+        (
+            "std.summary([[1, 2, 3], \"a\"], 1)",
+            "[[1, ... (+2 more)], ... (+1 more)]",
+        ),
+        ("std.summary({ \"k\": 1 }, 0)", "{ ... (+1 more) }"),
+        ("std.summary([1, 2])", "[1, 2]"),
+        ("std.summary(\"s\")", "'s'"),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::from_string(expected));
+    }
+
+    let result = nsi.evaluate_from_string("std.summary(big, -1)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::ValueError);
+}
+
+#[test]
+pub fn test_std_error() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.execute_from_string(
+        "let std = import(\"std\");\nfun f() {\n    std.error(\"boom\");\n}\nf();",
+    );
+    assert!(result.is_err(), "Statement should fail");
+
+    let e = result.unwrap_err();
+    assert_eq!(e.err_type, ErrorType::CustomError);
+    assert_eq!(e.msg, "boom");
+    assert_eq!(e.pos.map(|p| p.line), Some(2));
+
+    let result = nsi.execute_from_string(
+        "std.error({\"code\": 3, \"__str__\": fun(s) { return \"failed with \" + std.str(s.code); }});",
+    );
+    assert!(result.is_err(), "Statement should fail");
+
+    let e = result.unwrap_err();
+    assert_eq!(e.err_type, ErrorType::CustomError);
+    assert_eq!(
+        e.msg, "failed with 3",
+        "The message should use the `__str__` hook"
+    );
+}
+
+#[test]
+pub fn test_std_round() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for (expr, expected) in [
+        ("round(2.5)", Value::Int(3)),
+        ("round(-2.5)", Value::Int(-3)),
+        ("round(7)", Value::Int(7)),
+        ("round(1234.5, -2)", Value::Float(1200.0)),
+        ("round(5, 1)", Value::Float(5.0)),
+        ("roundHalfEven(2.5)", Value::Int(2)),
+        ("roundHalfEven(3.5)", Value::Int(4)),
+        ("roundHalfEven(0.125, 2)", Value::Float(0.12)),
+    ] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    let result = nsi.evaluate_from_string("import(\"std\").str(import(\"std\").round(3.14159, 2))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("3.14"));
+
+    for expr in ["round(\"1.5\")", "roundHalfEven(null)", "round(1.5, 1.0)"] {
+        let result = nsi.evaluate_from_string(&format!("import(\"std\").{}", expr));
+        assert!(result.is_err(), "Expression should fail");
+        assert!(matches!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(_)
+        ));
+    }
+
+    let result = nsi.evaluate_from_string("import(\"std\").round(1.5, -9223372036854775807 - 1)");
+    assert!(result.is_err(), "Precision of i64::MIN should fail");
+    assert!(matches!(
+        result.unwrap_err().err_type,
+        ErrorType::ValueError
+    ));
+
+    let result = nsi.evaluate_from_string("import(\"std\").round(1.5, 309)");
+    assert!(result.is_err(), "Precision beyond float range should fail");
+    assert!(matches!(
+        result.unwrap_err().err_type,
+        ErrorType::ValueError
+    ));
+
+    let result = nsi.evaluate_from_string("import(\"std\").round(1.5, -308)");
+    assert!(
+        result.is_ok(),
+        "Precision within float range should succeed"
+    );
+    assert_eq!(result.unwrap(), Value::Float(0.0));
+}
+
+#[test]
+pub fn test_builtin_methods() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let result = nsi.execute_from_string(
+        "let std = import(\"std\");
+        let nums = [1, 2, 3, 4];
+        let obj = { \"a\": 1, \"len\": fun() { return 99; } };",
+    );
+    assert!(result.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        (
+            "std.str([1, 2, 3].map(fun(x) { return x * 2; }))",
+            Value::from_string("[2, 4, 6]"),
+        ),
+        ("\"abc\".upper()", Value::from_string("ABC")),
+        (
+            "nums.filter(fun(x) { return x % 2 == 0; }).len()",
+            Value::Int(2),
+        ),
+        (
+            "std.str(\"a b\".split(\" \").map(fun(s) { return s.upper(); }))",
+            Value::from_string("['A', 'B']"),
+        ),
+        ("obj.len()", Value::Int(99)),
+        ("obj.keys().len()", Value::Int(2)),
+        ("nums.max()", Value::Int(4)),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    let result = nsi.execute_from_string("nums.append(5); nums.pop(); nums.pop();");
+    assert!(result.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("std.str(nums)");
+    assert_eq!(result.unwrap(), Value::from_string("[1, 2, 3]"));
+}
+
+#[test]
+pub fn test_builtin_methods_unknown() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    for (expr, type_name) in [("(5).foo()", "Int"), ("\"s\".nope()", "String")] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+        assert_eq!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(type_name)
+        );
+    }
+
+    for expr in ["{}.missing()", "[].map()"] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+    }
+}
+
+#[test]
+pub fn test_hash() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string("let {hash} = import(\"std\");");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for expr in [
+        "hash(\"abc\") == hash(\"ab\" + \"c\")",
+        "hash(42) == hash(40 + 2)",
+        "hash(null) == hash(null)",
+        "hash(1.5) == hash(3.0 / 2)",
+        "hash(1) != hash(2)",
+        "hash(true) != hash(false)",
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::Bool(true), "{}", expr);
+    }
+
+    for expr in ["hash([])", "hash({})", "hash(hash)"] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+        assert!(matches!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(_)
+        ));
+    }
+}
+
+#[test]
+pub fn test_format_placeholders() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string("let {format} = import(\"std\");");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("format(\"{} + {} = {}\", 1, 2, 3)", "1 + 2 = 3"),
+        ("format(\"{1} {0}\", \"world\", \"hello\")", "hello world"),
+        ("format(\"{0}{0}{1}\", \"a\", 2.5)", "aa2.5"),
+        ("format(\"{x}-{y}\", {\"x\": 5, \"y\": true})", "5-true"),
+        ("format(\"{{}} {}\", null)", "{} null"),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::from_string(expected));
+    }
+
+    for expr in [
+        "format(\"{} {}\", 1)",
+        "format(\"{2}\", 1, 2)",
+        "format(\"{z}\", {\"x\": 5})",
+        "format(\"{x}\", 5)",
+        "format(\"{0\", 1)",
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+        assert_eq!(result.unwrap_err().err_type, ErrorType::ValueError);
+    }
+}
+
+#[test]
+pub fn test_pop_remove_defaults() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string("let {pop, remove} = import(\"std\");");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("pop([], 0)", Value::Int(0)),
+        ("pop([4], 0)", Value::Int(4)),
+        ("remove({}, \"x\", -1)", Value::Int(-1)),
+        ("remove({\"x\": 2}, \"x\", -1)", Value::Int(2)),
+        ("remove({}, \"x\")", Value::Null),
+        ("remove([1], 3, 9)", Value::Int(9)),
+        ("[].pop(null)", Value::Null),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    for expr in ["pop([])", "remove([1], 3)", "pop([], 1, 2)"] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+    }
+}
+
+#[test]
+pub fn test_abs_sign_clamp() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string("let {abs, sign, clamp} = import(\"std\");");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("abs(-3)", Value::Int(3)),
+        ("abs(-2.5)", Value::Float(2.5)),
+        ("sign(-3.5)", Value::Int(-1)),
+        ("sign(0)", Value::Int(0)),
+        ("sign(7)", Value::Int(1)),
+        ("clamp(5, 0, 3)", Value::Int(3)),
+        ("clamp(-1, 0, 3)", Value::Int(0)),
+        ("clamp(1.5, 0, 3)", Value::Float(1.5)),
+        ("clamp(2, 2, 2)", Value::Int(2)),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), expected, "{}", expr);
+    }
+
+    let result = nsi.evaluate_from_string("abs(-9223372036854775807 - 1)");
+    assert!(
+        result.is_err(),
+        "Absolute value of i64::MIN should overflow"
+    );
+    assert!(matches!(
+        result.unwrap_err().err_type,
+        ErrorType::ArithmeticError(_)
+    ));
+
+    let result = nsi.evaluate_from_string("clamp(1, 3, 0)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::ValueError);
+
+    for expr in ["abs(\"1\")", "sign(null)", "clamp(1, \"0\", 3)"] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+        assert!(matches!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(_)
+        ));
+    }
+}
+
+#[test]
+pub fn test_str_hook() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string(
+        "let {str, format} = import(\"std\");
+        let p = {\"n\": 1, \"__str__\": fun(self) { return \"custom\"; }};
+        let q = {\"__str__\": fun(self) { return \"<\" + str(self.n) + \">\"; }, \"n\": 2};
+        let loop = {\"__str__\": fun(self) { return \"!\" + str(self); }};",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("str(p)", "custom"),
+        ("str([p, q])", "[custom, <2>]"),
+        ("format(\"{} and {}\", p, q)", "custom and <2>"),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), Value::from_string(expected));
+    }
+
+    let result = nsi.evaluate_from_string("str(loop)");
+    assert!(result.is_ok(), "Expression should succeed");
+    match result.unwrap() {
+        Value::String(s) => assert!(s.starts_with("!{ '__str__': <function")),
+        v => panic!("Expected string, recieved {:?}", v),
+    }
+
+    let result = nsi.evaluate_from_string("str({\"__str__\": fun(self) { return null + 1; }})");
+    assert!(result.is_err(), "Expression should fail");
+}
+
+#[test]
+pub fn test_code_points() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string("let {charCodeAt, codePoints, str} = import(\"std\");");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    for (expr, expected) in [
+        ("charCodeAt(\"A\", 0)", Value::Int(65)),
+        ("charCodeAt(\"aé€\", 2)", Value::Int(8364)),
+        ("\"héllo\".charCodeAt(1)", Value::Int(233)),
+        ("str(codePoints(\"aé\"))", Value::from_string("[97, 233]")),
+        ("str(\"\".codePoints())", Value::from_string("[]")),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_ok(), "Expression should succeed");
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    for (expr, err_type) in [
+        ("charCodeAt(\"aé\", 2)", ErrorType::IndexError(2)),
+        ("charCodeAt(\"a\", -1)", ErrorType::IndexError(-1i64 as u32)),
+        ("charCodeAt(\"a\", \"0\")", ErrorType::TypeError("String")),
+        ("codePoints(5)", ErrorType::TypeError("Int")),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+        assert_eq!(result.unwrap_err().err_type, err_type);
+    }
+}
+
+#[test]
+pub fn test_sort() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string(
+        "let {sort, str} = import(\"std\");
+        let pairs = [[2, \"b\"], [1, \"z\"], [2, \"a\"], [1]];
+        sort(pairs);
+        let words = [\"pear\", \"apple\", \"fig\"].sort();",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"pairs".to_string()).unwrap().repr(env),
+        "[[1], [1, 'z'], [2, 'a'], [2, 'b']]"
+    );
+    assert_eq!(
+        env.get_global(&"words".to_string()).unwrap().repr(env),
+        "['apple', 'fig', 'pear']"
+    );
+
+    let result = nsi.evaluate_from_string("str(sort([3, 1.5, -2, 1]))");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("[-2, 1, 1.5, 3]"));
+
+    for expr in ["sort([1, \"a\"])", "sort([[1], [\"a\"]])", "sort(\"abc\")"] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+        assert!(matches!(
+            result.unwrap_err().err_type,
+            ErrorType::TypeError(_)
+        ));
+    }
+}
+
+#[test]
+pub fn test_memoize() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+    let state = nsi.execute_from_string(
+        "let {memoize, map, gc} = import(\"std\");
+        let calls = 0;
+        fun square(x) { calls += 1; return [x * x]; }
+        let fast = memoize(square);
+        let first = fast(4);
+        gc();
+        let results = map([4, 3, 4, 3, 2], fun(x) { return fast(x)[0]; });
+        let same = first == fast(4);
+        let other = memoize(square);
+        other(4);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"results".to_string()).unwrap().repr(env),
+        "[16, 9, 16, 9, 4]"
+    );
+    assert_eq!(
+        env.get_global(&"same".to_string()).unwrap(),
+        &Value::Bool(true)
+    );
+    assert_eq!(
+        env.get_global(&"calls".to_string()).unwrap(),
+        &Value::Int(4)
+    );
+
+    for (expr, err_type) in [
+        ("fast([1])", ErrorType::TypeError("Array")),
+        ("memoize(5)", ErrorType::TypeError("Int")),
+    ] {
+        let result = nsi.evaluate_from_string(expr);
+        assert!(result.is_err(), "Expression should fail");
+        assert_eq!(result.unwrap_err().err_type, err_type);
+    }
+
+    let segments = nsi.environment().segments().len();
+    let result = nsi.evaluate_from_string("memoize(square)(5)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        nsi.environment().segments().len(),
+        segments,
+        "Wrappers should share one native segment"
+    );
+}
+
+#[test]
+pub fn test_stack_depth() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let {stackDepth} = import(\"std\");
+        fun depths(n) {
+            if n == 0 { return [stackDepth()]; }
+            return [stackDepth(), ...depths(n - 1)];
+        }
+        let top = stackDepth();
+        let nested = depths(3);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    let top = match env.get_global(&"top".to_string()).unwrap() {
+        Value::Int(n) => *n,
+        v => panic!("expected Int, got {}", v.type_name()),
+    };
+    assert_eq!(
+        env.get_global(&"nested".to_string()).unwrap().repr(env),
+        format!("[{}, {}, {}, {}]", top + 1, top + 2, top + 3, top + 4)
+    );
+}
+
+#[test]
+pub fn test_merge() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let {merge} = import(\"std\");
+        let a = {\"x\": 1, \"n\": {\"p\": 1, \"q\": 2}};
+        let b = {\"y\": 2, \"x\": 9, \"n\": {\"p\": 5}};
+        let m = merge(a, b);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("[m.x, m.y]");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap().repr(nsi.environment()),
+        "[9, 2]",
+        "Keys of the second object should win"
+    );
+
+    let result = nsi.evaluate_from_string("[m.n.p, m.n.q]");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap().repr(nsi.environment()),
+        "[5, null]",
+        "Nested objects should be replaced, not merged"
+    );
+
+    let result = nsi.evaluate_from_string("[a.x, a.n.q]");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap().repr(nsi.environment()),
+        "[1, 2]",
+        "Inputs should be unchanged"
+    );
+
+    let result = nsi.evaluate_from_string("merge(a, 1)");
+    assert!(result.is_err(), "Non-object argument should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Int"));
+
+    let result = nsi.evaluate_from_string("merge([], b)");
+    assert!(result.is_err(), "Non-object receiver should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Array"));
+}
+
+#[test]
+pub fn test_deep_merge() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let {deepMerge} = import(\"std\");
+        let a = {\"x\": 1, \"n\": {\"p\": 1, \"q\": {\"r\": 1}}};
+        let b = {\"x\": 9, \"n\": {\"p\": 5, \"q\": {\"s\": 2}}};
+        let m = a.deepMerge(b);
+        a.self = a;
+        let cyclic = deepMerge(a, {\"self\": a});",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("[m.x, m.n.p, m.n.q.r, m.n.q.s]");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap().repr(nsi.environment()),
+        "[9, 5, 1, 2]",
+        "Nested objects should be merged"
+    );
+
+    let result = nsi.evaluate_from_string("a.n.q.s");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::Null, "Inputs should be unchanged");
+
+    let result = nsi.evaluate_from_string("cyclic.self.self == a");
+    assert!(result.is_ok(), "Cyclic input should merge");
+    assert_eq!(result.unwrap(), Value::Bool(true));
+
+    let result = nsi.evaluate_from_string("deepMerge({}, null)");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Null"));
+}
+
+#[test]
+pub fn test_repeat() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string("let {repeat} = import(\"std\");");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("repeat(\"ab\", 3)");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("ababab"));
+
+    let result = nsi.evaluate_from_string("\"é\".repeat(2)");
+    assert!(result.is_ok(), "Method call should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("éé"));
+
+    let result = nsi.evaluate_from_string("repeat(\"ab\", 0)");
+    assert!(result.is_ok(), "Zero count should succeed");
+    assert_eq!(result.unwrap(), Value::from_string(""));
+
+    let result = nsi.evaluate_from_string("repeat(\"ab\", -2)");
+    assert!(result.is_ok(), "Negative count should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string(""),
+        "Negative count should give an empty string"
+    );
+
+    let result = nsi.evaluate_from_string("repeat(3, 3)");
+    assert!(result.is_err(), "Non-string receiver should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Int"));
+
+    let result = nsi.evaluate_from_string("repeat(\"ab\", \"3\")");
+    assert!(result.is_err(), "Non-integer count should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("String"));
+}
+
+#[test]
+pub fn test_pad() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string("let {padLeft, padRight} = import(\"std\");");
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let result = nsi.evaluate_from_string("padLeft(\"5\", 3, \"0\")");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("005"));
+
+    let result = nsi.evaluate_from_string("padRight(\"5\", 3, \"0\")");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("500"));
+
+    let result = nsi.evaluate_from_string("\"ab\".padRight(4, \".\")");
+    assert!(result.is_ok(), "Method call should succeed");
+    assert_eq!(result.unwrap(), Value::from_string("ab.."));
+
+    let result = nsi.evaluate_from_string("padLeft(\"héllo\", 6, \"*\")");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("*héllo"),
+        "Width should count characters, not bytes"
+    );
+
+    let result = nsi.evaluate_from_string("padLeft(\"long\", 2, \" \")");
+    assert!(result.is_ok(), "Expression should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("long"),
+        "Strings at least as wide should be unchanged"
+    );
+
+    let result = nsi.evaluate_from_string("padRight(\"x\", -1, \" \")");
+    assert!(result.is_ok(), "Negative width should succeed");
+    assert_eq!(
+        result.unwrap(),
+        Value::from_string("x"),
+        "Negative width should leave the string unchanged"
+    );
+
+    let result = nsi.evaluate_from_string("padLeft(5, 3, \"0\")");
+    assert!(result.is_err(), "Non-string receiver should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Int"));
+
+    let result = nsi.evaluate_from_string("padLeft(\"5\", \"3\", \"0\")");
+    assert!(result.is_err(), "Non-integer width should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("String"));
+
+    let result = nsi.evaluate_from_string("padRight(\"5\", 3, 0)");
+    assert!(result.is_err(), "Non-string fill should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("Int"));
+
+    let result = nsi.evaluate_from_string("padRight(\"5\", 3, \"ab\")");
+    assert!(result.is_err(), "Multi-character fill should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::ValueError);
+
+    let result = nsi.evaluate_from_string("padLeft(\"5\", 3, \"\")");
+    assert!(result.is_err(), "Empty fill should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::ValueError);
+}
+
+#[test]
+pub fn test_mutators_return_receiver() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let std = import(\"std\");
+        let arr = [3];
+        let chained = arr.append(1).append(2).sort();
+        let same = chained == arr;
+        let flipped = arr.insert(0, 0).reverse();
+        let obj = std.insert({}, \"k\", 1);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"arr".to_string()).unwrap().repr(env),
+        "[3, 2, 1, 0]"
+    );
+    assert_eq!(
+        env.get_global(&"same".to_string()).unwrap(),
+        &Value::Bool(true)
+    );
+    assert_eq!(
+        env.get_global(&"flipped".to_string()).unwrap(),
+        env.get_global(&"arr".to_string()).unwrap()
+    );
+    assert_eq!(
+        env.get_global(&"obj".to_string()).unwrap().repr(env),
+        "{ 'k': 1 }"
+    );
+
+    let result = nsi.evaluate_from_string("std.reverse(\"abc\")");
+    assert!(result.is_err(), "Expression should fail");
+    assert_eq!(result.unwrap_err().err_type, ErrorType::TypeError("String"));
+}
@@ -66,3 +66,299 @@ pub fn test_while_continue_invalid_position() {
 
     assert_eq!(state.unwrap_err().err_type, ErrorType::SyntaxError);
 }
+
+#[test]
+pub fn test_while_infinite_loop_rejected() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    for program in [
+        "while true {}",
+        "while 1 && !null { let x = 1; }",
+        "while true { while true { break; } }",
+        "while true { fun f() { return 1; } }",
+    ] {
+        let state = nsi.execute_from_string(program);
+        assert!(state.is_err(), "Statement should fail");
+        assert_eq!(state.unwrap_err().err_type, ErrorType::CompilerError);
+    }
+}
+
+#[test]
+pub fn test_while_constant_condition_with_exit() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "while true { break; }
+        let x = 0;
+        while true { if x > 3 { break; } x += 1; }
+        fun f() { while \"yes\" { while x < 10 { x += 1; } return x; } }
+        let y = f();",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let val = nsi.environment().get_global(&"y".to_string());
+    assert_eq!(val.unwrap(), &Value::Int(10));
+}
+
+#[test]
+pub fn test_for_zip_pairs() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let {zip} = import(\"std\");
+        let total = 0;
+        for (a, b) in zip([1, 2, 3], [10, 20, 30, 40]) { total += a + b; }
+        fun dot(xs, ys) {
+            let s = 0;
+            for (x, y) in zip(xs, ys) { s += x * y; }
+            return s;
+        }
+        let d = dot([1, 2, 3], [4, 5]);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"total".to_string()).unwrap(),
+        &Value::Int(66)
+    );
+    assert_eq!(env.get_global(&"d".to_string()).unwrap(), &Value::Int(14));
+}
+
+#[test]
+pub fn test_for_break_continue() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let s = \"\";
+        for c in \"abcde\" { if c == \"b\" { continue; } if c == \"d\" { break; } s += c; }
+        let n = 0;
+        for x in [1, 2] { for y in [3, 4] { n += x * y; } }",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"s".to_string()).unwrap(),
+        &Value::from_string("ac")
+    );
+    assert_eq!(env.get_global(&"n".to_string()).unwrap(), &Value::Int(21));
+
+    let state = nsi.execute_from_string("for x in 5 {}");
+    assert!(state.is_err(), "Statement should fail");
+    assert!(matches!(
+        state.unwrap_err().err_type,
+        ErrorType::TypeError(_)
+    ));
+}
+
+#[test]
+pub fn test_for_targets_scoped_to_body() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let x = 10;
+        let seen = [];
+        for x in [1, 2] { seen.append(x); }
+        for (x, i) in [[3, 4]] { seen.append(x + i); }
+        for i in 0..2 { seen.append(i); }
+        fun f() {
+            let y = 5;
+            let s = 0;
+            for y in 0..3 { s += y; }
+            return [y, s];
+        }
+        let r = f();
+        let cs = [];
+        for c in \"añb€\" { cs.append(c); }",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"x".to_string()).unwrap(),
+        &Value::Int(10),
+        "Outer x should be unchanged by loops binding x"
+    );
+    assert!(
+        env.get_global(&"i".to_string()).is_none(),
+        "Loop variable should not leak past the loop"
+    );
+    assert_eq!(
+        env.get_global(&"seen".to_string()).unwrap().repr(env),
+        "[1, 2, 7, 0, 1]"
+    );
+    assert_eq!(
+        env.get_global(&"r".to_string()).unwrap().repr(env),
+        "[5, 3]"
+    );
+    assert_eq!(
+        env.get_global(&"cs".to_string()).unwrap().repr(env),
+        "['a', 'ñ', 'b', '€']"
+    );
+
+    let state = nsi.execute_from_string("let i = 1;");
+    assert!(
+        state.is_ok(),
+        "Declaring a former loop variable should succeed"
+    );
+}
+
+#[test]
+pub fn test_while_let_binding() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let stack = [1, 2, 0, 4];
+        fun next() { return if stack.isEmpty() { null } else { stack.pop() }; }
+        let total = 0;
+        let count = 0;
+        while let v = next() { total += v; count += 1; }
+        fun drain(arr) {
+            let s = 0;
+            while let x = if arr.isEmpty() { null } else { arr.pop() } { s = s * 10 + x; }
+            return s;
+        }
+        let d = drain([3, 2, 1]);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"total".to_string()).unwrap(),
+        &Value::Int(7)
+    );
+    assert_eq!(
+        env.get_global(&"count".to_string()).unwrap(),
+        &Value::Int(4)
+    );
+    assert!(
+        env.get_global(&"v".to_string()).is_none(),
+        "Binding should not outlive the loop"
+    );
+    assert_eq!(env.get_global(&"d".to_string()).unwrap(), &Value::Int(123));
+
+    let state = nsi.execute_from_string(
+        "let w = [1];
+        let n = 0;
+        while let w = if w.isEmpty() { null } else { w.pop() } { n += w; }",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(env.get_global(&"n".to_string()).unwrap(), &Value::Int(1));
+    assert_eq!(
+        env.get_global(&"w".to_string()).unwrap().repr(env),
+        "[]",
+        "Outer variable should be unchanged by the binding"
+    );
+}
+
+#[test]
+pub fn test_for_range_continue() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let s = 0;
+        for i in 0..10 { if i % 3 == 0 { continue; } s += i; }
+        fun odds(n) {
+            let out = [];
+            for i in 0..n { if i % 2 == 0 { continue; } out.append(i); i = n; }
+            return out;
+        }
+        let o = odds(7);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(env.get_global(&"s".to_string()).unwrap(), &Value::Int(27));
+    assert_eq!(
+        env.get_global(&"o".to_string()).unwrap().repr(env),
+        "[1, 3, 5]"
+    );
+}
+
+#[test]
+pub fn test_for_range_step() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let down = [];
+        for i in 10..0 step -1 { down.append(i); }
+        let step = 3;
+        let evens = [];
+        for i in 0..7 step 2 { evens.append(i); }
+        fun countdown(n) {
+            let out = [];
+            for i in n..0 step -2 { if i == 6 { continue; } out.append(i); }
+            return out;
+        }
+        let c = countdown(8);",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    assert_eq!(
+        env.get_global(&"down".to_string()).unwrap().repr(env),
+        "[10, 9, 8, 7, 6, 5, 4, 3, 2, 1]"
+    );
+    assert_eq!(
+        env.get_global(&"evens".to_string()).unwrap().repr(env),
+        "[0, 2, 4, 6]"
+    );
+    assert_eq!(
+        env.get_global(&"c".to_string()).unwrap().repr(env),
+        "[8, 4, 2]"
+    );
+    assert_eq!(env.get_global(&"step".to_string()).unwrap(), &Value::Int(3));
+}
+
+#[test]
+pub fn test_for_range_invalid_step() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    for stmt in [
+        "for i in 0..5 step 0 { }",
+        "let s = 1; for i in 0..5 step s { }",
+    ] {
+        let state = nsi.execute_from_string(stmt);
+        assert!(state.is_err(), "Statement should fail");
+        assert_eq!(state.unwrap_err().err_type, ErrorType::CompilerError);
+    }
+}
+
+#[test]
+pub fn test_for_range_overflow_terminates() {
+    let mut nsi = Interpreter::new(false, false, vec![]);
+
+    let state = nsi.execute_from_string(
+        "let max = 9223372036854775807;
+        let min = -max - 1;
+        let last = [];
+        for i in (max - 3)..max { last.append(i - max); }
+        let up = [];
+        for i in (max - 5)..max step 2 { up.append(i - max); }
+        let down = [];
+        for i in (min + 5)..min step -2 { down.append(i - min); }
+        fun local() {
+            let out = [];
+            for i in (max - 3)..max step 3 { out.append(i - max); }
+            return out;
+        }
+        let inner = local();",
+    );
+    assert!(state.is_ok(), "Statement should succeed");
+
+    let env = nsi.environment();
+    for (name, expected) in [
+        ("last", "[-3, -2, -1]"),
+        ("up", "[-5, -3, -1]"),
+        ("down", "[5, 3, 1]"),
+        ("inner", "[-3]"),
+    ] {
+        assert_eq!(
+            env.get_global(&name.to_string()).unwrap().repr(env),
+            expected
+        );
+    }
+}